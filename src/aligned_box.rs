@@ -0,0 +1,274 @@
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error, Layout},
+    boxed::Box,
+};
+use core::{
+    cell::Cell,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem::{align_of, size_of, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::AtomicUsize,
+};
+
+use crate::{
+    max,
+    thin_box::{alloc_with_header, dealloc_with_header, header},
+    thin_rc::Count,
+    MaybeOwned, Pointer, ADDRESS_HIGH_BITS,
+};
+
+
+const fn layout<T, const N: usize>() -> Layout {
+    match Layout::from_size_align(size_of::<T>(), max(align_of::<T>(), N)) {
+        Ok(x) => x,
+        Err(_) => panic!("alignment must be a power of two"),
+    }
+}
+
+
+/// A [`Box`] allocated with at least `N` alignment, leaving more low bits free for tags.
+pub struct AlignedBox<T, const N: usize>(*const (), PhantomData<T>);
+
+impl<T, const N: usize> AlignedBox<T, N> {
+    const LAYOUT: Layout = layout::<T, N>();
+
+    pub fn new(value: T) -> Self {
+        let ptr = if Self::LAYOUT.size() == 0 {
+            ptr::without_provenance_mut(Self::LAYOUT.align())
+        } else {
+            let ptr = unsafe { alloc(Self::LAYOUT) };
+            if ptr.is_null() {
+                handle_alloc_error(Self::LAYOUT);
+            }
+
+            ptr
+        };
+
+        unsafe { ptr.cast::<T>().write(value) };
+        Self(ptr.cast_const().cast(), PhantomData)
+    }
+
+    pub fn into_inner(self) -> T {
+        let value = ManuallyDrop::new(self);
+
+        unsafe {
+            let inner = value.0.cast::<T>().read();
+            value.dealloc();
+            inner
+        }
+    }
+
+    /// Free the allocation without dropping its contents.
+    unsafe fn dealloc(&self) {
+        if Self::LAYOUT.size() != 0 {
+            unsafe { dealloc(self.0.cast_mut().cast(), Self::LAYOUT) };
+        }
+    }
+}
+
+unsafe impl<T: Send, const N: usize> Send for AlignedBox<T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for AlignedBox<T, N> {}
+
+impl<T, const N: usize> Drop for AlignedBox<T, N> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.0.cast::<T>().cast_mut());
+            self.dealloc();
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for AlignedBox<T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.0.cast() }
+    }
+}
+
+impl<T, const N: usize> DerefMut for AlignedBox<T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.0.cast_mut().cast() }
+    }
+}
+
+impl<T, const N: usize> From<T> for AlignedBox<T, N> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T, const N: usize> From<Box<T>> for AlignedBox<T, N> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(*value)
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for AlignedBox<T, N> {
+    fn clone(&self) -> Self {
+        Self::new((**self).clone())
+    }
+}
+
+impl<T: Default, const N: usize> Default for AlignedBox<T, N> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for AlignedBox<T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for AlignedBox<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for AlignedBox<T, N> {}
+
+impl<T: Hash, const N: usize> Hash for AlignedBox<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+unsafe impl<T, const N: usize> Pointer for AlignedBox<T, N> {
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = Self::LAYOUT.align();
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
+
+
+macro_rules! aligned_rc {
+    ($(#[$attr:meta])* $name:ident, $count:ty) => {
+        $(#[$attr])*
+        pub struct $name<T, const N: usize>(*const (), PhantomData<T>);
+
+        impl<T, const N: usize> $name<T, N> {
+            const LAYOUT: Layout = layout::<T, N>();
+
+            pub fn new(value: T) -> Self {
+                let ptr = alloc_with_header(Self::LAYOUT, <$count>::one());
+                unsafe { ptr.cast_mut().cast::<T>().write(value) };
+                Self(ptr, PhantomData)
+            }
+
+            pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+                this.0 == other.0
+            }
+
+            fn count(&self) -> &$count {
+                unsafe { &*header(self.0) }
+            }
+        }
+
+        impl<T, const N: usize> Drop for $name<T, N> {
+            fn drop(&mut self) {
+                if !self.count().decrement() {
+                    return;
+                }
+
+                unsafe {
+                    ptr::drop_in_place(self.0.cast::<T>().cast_mut());
+                    dealloc_with_header::<$count>(self.0, Self::LAYOUT);
+                }
+            }
+        }
+
+        impl<T, const N: usize> Clone for $name<T, N> {
+            fn clone(&self) -> Self {
+                self.count().increment();
+                Self(self.0, PhantomData)
+            }
+        }
+
+        impl<T, const N: usize> Deref for $name<T, N> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                unsafe { &*self.0.cast() }
+            }
+        }
+
+        impl<T, const N: usize> From<T> for $name<T, N> {
+            fn from(value: T) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl<T, const N: usize> From<Box<T>> for $name<T, N> {
+            fn from(value: Box<T>) -> Self {
+                Self::new(*value)
+            }
+        }
+
+        impl<T: Default, const N: usize> Default for $name<T, N> {
+            fn default() -> Self {
+                Self::new(T::default())
+            }
+        }
+
+        impl<T: Debug, const N: usize> Debug for $name<T, N> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                (**self).fmt(f)
+            }
+        }
+
+        impl<T: PartialEq, const N: usize> PartialEq for $name<T, N> {
+            fn eq(&self, other: &Self) -> bool {
+                **self == **other
+            }
+        }
+
+        impl<T: Eq, const N: usize> Eq for $name<T, N> {}
+
+        impl<T: Hash, const N: usize> Hash for $name<T, N> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                (**self).hash(state);
+            }
+        }
+
+        unsafe impl<T, const N: usize> Pointer for $name<T, N> {
+            const NON_NULL: bool = true;
+            const ALIGNMENT: usize = max(Self::LAYOUT.align(), align_of::<$count>());
+            const CLONE_IN_PLACE: bool = true;
+            const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+            fn into_ptr(value: Self) -> *const () {
+                ManuallyDrop::new(value).0
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+                MaybeOwned::new(Self(ptr, PhantomData))
+            }
+        }
+    };
+}
+
+aligned_rc! {
+    /// An [`Rc`](alloc::rc::Rc) allocated with at least `N` alignment.
+    AlignedRc, Cell<usize>
+}
+
+aligned_rc! {
+    /// An [`Arc`](alloc::sync::Arc) allocated with at least `N` alignment.
+    AlignedArc, AtomicUsize
+}
+
+unsafe impl<T: Send + Sync, const N: usize> Send for AlignedArc<T, N> {}
+unsafe impl<T: Send + Sync, const N: usize> Sync for AlignedArc<T, N> {}
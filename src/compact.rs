@@ -4,9 +4,10 @@ use core::{
     marker::PhantomData,
     mem::ManuallyDrop,
     ops::{Deref, DerefMut},
+    pin::Pin,
 };
 
-use crate::{MaybeOwned, Pointer};
+use crate::{MaybeOwned, Pointer, Tag, TagField};
 
 
 pub struct Compact<P: Pointer>(*const (), PhantomData<P>);
@@ -21,7 +22,7 @@ impl<P: Pointer> Compact<P> {
     }
 
     pub fn get_ref(&self) -> Ref<P> {
-        Ref(unsafe { P::from_ptr(self.0) }, PhantomData)
+        unsafe { Ref::from_ptr(self.0) }
     }
 
     pub fn get_mut(&mut self) -> RefMut<P> {
@@ -40,6 +41,14 @@ impl<P: Pointer> Compact<P> {
         self.0 = P::into_ptr(value);
         old
     }
+
+    pub fn get_tag<T: Tag>(&self, field: TagField<P, T>) -> T {
+        unsafe { field.get(self.0) }
+    }
+
+    pub fn set_tag<T: Tag>(&mut self, field: TagField<P, T>, value: T) {
+        self.0 = field.set(self.0, value);
+    }
 }
 
 impl<P: Pointer + Clone> Compact<P> {
@@ -48,6 +57,13 @@ impl<P: Pointer + Clone> Compact<P> {
     }
 }
 
+impl<P: Pointer + DerefMut> Compact<Pin<P>> {
+    /// Like [`Compact::get_mut`], but the pointee is only handed out pinned.
+    pub fn get_pin_mut(&mut self) -> PinRefMut<P> {
+        PinRefMut(self.get_mut())
+    }
+}
+
 impl<P: Pointer> Drop for Compact<P> {
     fn drop(&mut self) {
         unsafe { P::from_ptr(self.0).assume_owned() };
@@ -70,6 +86,12 @@ impl<P: Pointer + Clone> Clone for Compact<P> {
 
 pub struct Ref<'a, P>(MaybeOwned<P>, PhantomData<&'a P>);
 
+impl<'a, P: Pointer> Ref<'a, P> {
+    pub(crate) unsafe fn from_ptr(ptr: *const ()) -> Self {
+        Self(unsafe { P::from_ptr(ptr) }, PhantomData)
+    }
+}
+
 impl<'a, P: Pointer> Deref for Ref<'a, P> {
     type Target = P;
 
@@ -118,3 +140,20 @@ impl<'a, P: Pointer> BorrowMut<P> for RefMut<'a, P> {
         self.0.borrow_mut()
     }
 }
+
+
+pub struct PinRefMut<'a, P: Pointer + Deref>(RefMut<'a, Pin<P>>);
+
+impl<'a, P: Pointer + DerefMut> PinRefMut<'a, P> {
+    pub fn as_mut(&mut self) -> Pin<&mut P::Target> {
+        self.0.as_mut()
+    }
+}
+
+impl<'a, P: Pointer + Deref> Deref for PinRefMut<'a, P> {
+    type Target = P::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -0,0 +1,181 @@
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+use core::{fmt::Debug, ptr};
+
+use crate::{compact::Compact, small_int::SmallInt, thin_rc::ThinArc, Null};
+
+
+/// Integers which fit into a [`CompactValue`].
+pub type Int = SmallInt<{ usize::BITS - 2 }>;
+
+pub type Array = Vec<CompactValue>;
+pub type Map = BTreeMap<String, CompactValue>;
+
+type Scalar = Result<Result<Null, bool>, Int>;
+type Heap = Result<ThinArc<str>, Result<Box<Array>, Box<Map>>>;
+
+/// A JSON-like dynamic value which is a single pointer wide.
+///
+/// Scalars are stored inline, strings are shared and arrays and maps are boxed.
+pub struct CompactValue(Compact<Result<Scalar, Heap>>);
+
+/// A borrowed view into a [`CompactValue`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRef<'a> {
+    Null,
+    Bool(bool),
+    Int(isize),
+    Str(&'a str),
+    Array(&'a [CompactValue]),
+    Map(&'a Map),
+}
+
+impl CompactValue {
+    pub fn null() -> Self {
+        Self(Compact::new(Ok(Ok(Ok(Null)))))
+    }
+
+    /// Returns `None` if `value` does not fit into [`Int`].
+    pub fn int(value: isize) -> Option<Self> {
+        Int::new(value).map(Self::from)
+    }
+
+    pub fn view(&self) -> ValueRef<'_> {
+        let value = self.0.get_ref();
+
+        // The referenced heap data lives as long as `self`, not just the `Ref`.
+        match &*value {
+            Ok(Ok(Ok(Null))) => ValueRef::Null,
+            Ok(Ok(Err(x))) => ValueRef::Bool(*x),
+            Ok(Err(x)) => ValueRef::Int(x.value()),
+            Err(Ok(x)) => ValueRef::Str(unsafe { &*ptr::from_ref(&**x) }),
+            Err(Err(Ok(x))) => ValueRef::Array(unsafe { &*ptr::from_ref(x.as_slice()) }),
+            Err(Err(Err(x))) => ValueRef::Map(unsafe { &*ptr::from_ref(&**x) }),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.view(), ValueRef::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.view() {
+            ValueRef::Bool(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<isize> {
+        match self.view() {
+            ValueRef::Int(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self.view() {
+            ValueRef::Str(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[CompactValue]> {
+        match self.view() {
+            ValueRef::Array(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&Map> {
+        match self.view() {
+            ValueRef::Map(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+unsafe impl Send for CompactValue {}
+unsafe impl Sync for CompactValue {}
+
+impl Default for CompactValue {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl Clone for CompactValue {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl From<bool> for CompactValue {
+    fn from(value: bool) -> Self {
+        Self(Compact::new(Ok(Ok(Err(value)))))
+    }
+}
+
+impl From<Int> for CompactValue {
+    fn from(value: Int) -> Self {
+        Self(Compact::new(Ok(Err(value))))
+    }
+}
+
+impl From<ThinArc<str>> for CompactValue {
+    fn from(value: ThinArc<str>) -> Self {
+        Self(Compact::new(Err(Ok(value))))
+    }
+}
+
+impl From<&str> for CompactValue {
+    fn from(value: &str) -> Self {
+        ThinArc::from(value).into()
+    }
+}
+
+impl From<String> for CompactValue {
+    fn from(value: String) -> Self {
+        ThinArc::from(value).into()
+    }
+}
+
+impl From<Array> for CompactValue {
+    fn from(value: Array) -> Self {
+        Self(Compact::new(Err(Err(Ok(Box::new(value))))))
+    }
+}
+
+impl From<Map> for CompactValue {
+    fn from(value: Map) -> Self {
+        Self(Compact::new(Err(Err(Err(Box::new(value))))))
+    }
+}
+
+impl<T: Into<CompactValue>> From<Option<T>> for CompactValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or_else(Self::null, Into::into)
+    }
+}
+
+impl FromIterator<CompactValue> for CompactValue {
+    fn from_iter<I: IntoIterator<Item = CompactValue>>(iter: I) -> Self {
+        Array::from_iter(iter).into()
+    }
+}
+
+impl FromIterator<(String, CompactValue)> for CompactValue {
+    fn from_iter<I: IntoIterator<Item = (String, CompactValue)>>(iter: I) -> Self {
+        Map::from_iter(iter).into()
+    }
+}
+
+impl Debug for CompactValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.view().fmt(f)
+    }
+}
+
+impl PartialEq for CompactValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.view() == other.view()
+    }
+}
@@ -1,17 +1,26 @@
 use alloc::{
-    boxed::Box,
     rc,
     rc::Rc,
     sync::{self, Arc},
 };
-use core::{mem::align_of, sync::atomic::AtomicUsize};
+use core::ptr;
+
+// With `allocator_api` the impls for any zero-sized allocator cover the global one.
+#[cfg(not(feature = "allocator_api"))]
+use {
+    crate::max,
+    alloc::boxed::Box,
+    core::{mem::align_of, sync::atomic::AtomicUsize},
+};
 
-use crate::{max, MaybeOwned, Pointer};
+use crate::{MaybeOwned, Pointer, ADDRESS_HIGH_BITS};
 
 
+#[cfg(not(feature = "allocator_api"))]
 unsafe impl<T> Pointer for Box<T> {
     const NON_NULL: bool = true;
     const ALIGNMENT: usize = align_of::<T>();
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         Box::into_raw(value) as *const ()
@@ -23,10 +32,12 @@ unsafe impl<T> Pointer for Box<T> {
 }
 
 
+#[cfg(not(feature = "allocator_api"))]
 unsafe impl<T> Pointer for Rc<T> {
     const NON_NULL: bool = true;
     const ALIGNMENT: usize = max(align_of::<usize>(), align_of::<T>());
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         Rc::into_raw(value).cast()
@@ -38,23 +49,37 @@ unsafe impl<T> Pointer for Rc<T> {
 }
 
 
+/// The dangling [`rc::Weak::new`] sentinel is stored as null, so real weak pointers keep the
+/// alignment of [`Rc`].
 unsafe impl<T> Pointer for rc::Weak<T> {
+    const ALIGNMENT: usize = <Rc<T> as Pointer>::ALIGNMENT;
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
-        rc::Weak::into_raw(value).cast()
+        if rc::Weak::ptr_eq(&value, &rc::Weak::new()) {
+            ptr::null()
+        } else {
+            rc::Weak::into_raw(value).cast()
+        }
     }
 
     unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
-        MaybeOwned::new(unsafe { rc::Weak::from_raw(ptr.cast()) })
+        if ptr.is_null() {
+            MaybeOwned::new(rc::Weak::new())
+        } else {
+            MaybeOwned::new(unsafe { rc::Weak::from_raw(ptr.cast()) })
+        }
     }
 }
 
 
+#[cfg(not(feature = "allocator_api"))]
 unsafe impl<T> Pointer for Arc<T> {
     const NON_NULL: bool = true;
     const ALIGNMENT: usize = max(align_of::<AtomicUsize>(), align_of::<T>());
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         Arc::into_raw(value).cast()
@@ -66,14 +91,26 @@ unsafe impl<T> Pointer for Arc<T> {
 }
 
 
+/// The dangling [`sync::Weak::new`] sentinel is stored as null, so real weak pointers keep the
+/// alignment of [`Arc`].
 unsafe impl<T> Pointer for sync::Weak<T> {
+    const ALIGNMENT: usize = <Arc<T> as Pointer>::ALIGNMENT;
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
-        sync::Weak::into_raw(value).cast()
+        if sync::Weak::ptr_eq(&value, &sync::Weak::new()) {
+            ptr::null()
+        } else {
+            sync::Weak::into_raw(value).cast()
+        }
     }
 
     unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
-        MaybeOwned::new(unsafe { sync::Weak::from_raw(ptr.cast()) })
+        if ptr.is_null() {
+            MaybeOwned::new(sync::Weak::new())
+        } else {
+            MaybeOwned::new(unsafe { sync::Weak::from_raw(ptr.cast()) })
+        }
     }
 }
@@ -0,0 +1,77 @@
+use alloc::{boxed::Box, rc::Rc, sync::Arc};
+use core::{
+    alloc::Allocator,
+    mem::{self, align_of},
+    sync::atomic::AtomicUsize,
+};
+
+use crate::{max, Eval, MaybeOwned, Pointer, ZeroSized, ADDRESS_HIGH_BITS};
+
+
+/// Only zero-sized allocators are supported, a new handle is created with [`Default`] when
+/// decoding.
+unsafe impl<T, A> Pointer for Box<T, A>
+where
+    A: Allocator + Default,
+    ZeroSized<A>: Eval<RESULT = true>,
+{
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = align_of::<T>();
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        let (ptr, alloc) = Box::into_raw_with_allocator(value);
+        mem::forget(alloc);
+        ptr.cast_const().cast()
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(unsafe { Box::from_raw_in(ptr.cast_mut().cast(), A::default()) })
+    }
+}
+
+
+/// [`rc::Weak`](alloc::rc::Weak) is still only supported with the global allocator.
+unsafe impl<T, A> Pointer for Rc<T, A>
+where
+    A: Allocator + Default,
+    ZeroSized<A>: Eval<RESULT = true>,
+{
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = max(align_of::<usize>(), align_of::<T>());
+    const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        let (ptr, alloc) = Rc::into_raw_with_allocator(value);
+        mem::forget(alloc);
+        ptr.cast()
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(unsafe { Rc::from_raw_in(ptr.cast(), A::default()) })
+    }
+}
+
+
+/// [`sync::Weak`](alloc::sync::Weak) is still only supported with the global allocator.
+unsafe impl<T, A> Pointer for Arc<T, A>
+where
+    A: Allocator + Default,
+    ZeroSized<A>: Eval<RESULT = true>,
+{
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = max(align_of::<AtomicUsize>(), align_of::<T>());
+    const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        let (ptr, alloc) = Arc::into_raw_with_allocator(value);
+        mem::forget(alloc);
+        ptr.cast()
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(unsafe { Arc::from_raw_in(ptr.cast(), A::default()) })
+    }
+}
@@ -2,13 +2,14 @@ use core::{mem::align_of, sync::atomic::AtomicUsize};
 
 use triomphe::{Arc, ThinArc};
 
-use crate::{max, MaybeOwned, Pointer};
+use crate::{max, MaybeOwned, Pointer, ADDRESS_HIGH_BITS};
 
 
 unsafe impl<T> Pointer for Arc<T> {
     const NON_NULL: bool = true;
     const ALIGNMENT: usize = max(align_of::<AtomicUsize>(), align_of::<T>());
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         Arc::into_raw(value).cast()
@@ -23,6 +24,7 @@ unsafe impl<T> Pointer for Arc<T> {
 unsafe impl<H, T> Pointer for ThinArc<H, T> {
     const NON_NULL: bool = true;
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     const ALIGNMENT: usize = max(
         align_of::<AtomicUsize>(),
@@ -0,0 +1,191 @@
+use alloc::boxed::Box;
+use core::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem::{size_of, ManuallyDrop},
+    num::{
+        NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU16, NonZeroU32,
+        NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    ptr,
+};
+
+use crate::{MaybeOwned, Pointer, INLINE};
+
+
+/// [`Copy`] types which can be stored as the plain bytes of an integer.
+///
+/// # Safety
+///
+/// The type must not contain padding bytes, which would be uninitialized in the word, nor
+/// pointers, whose provenance is lost when stored inline.
+pub unsafe trait Inline: Copy {}
+
+macro_rules! inline {
+    ($($ty:ty),+) => {
+        $(unsafe impl Inline for $ty {})+
+    };
+}
+
+inline!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroUsize,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroIsize
+);
+
+unsafe impl<T: Inline, const N: usize> Inline for [T; N] {}
+unsafe impl<T: Inline> Inline for (T,) {}
+unsafe impl<T: Inline> Inline for (T, T) {}
+unsafe impl<T: Inline> Inline for (T, T, T) {}
+unsafe impl<T: Inline> Inline for (T, T, T, T) {}
+
+
+/// A value stored next to the tag byte if it fits, otherwise in a [`Box`].
+///
+/// The choice is made for each value: read as an integer, it must leave the top byte clear. Values
+/// of types smaller than a word always fit, larger types are always boxed. [`InlineOrBox::get`]
+/// returns a copy, since inline values are shifted within the word.
+pub struct InlineOrBox<T: Inline>(*const (), PhantomData<T>);
+
+#[repr(align(8))]
+struct Boxed<T>(T);
+
+impl<T: Inline> InlineOrBox<T> {
+    /// Position of the value in the bytes of a word, so that it reads as a zero-extended integer.
+    #[cfg(target_endian = "little")]
+    const OFFSET: usize = 0;
+    #[cfg(target_endian = "big")]
+    const OFFSET: usize = size_of::<usize>().saturating_sub(size_of::<T>());
+
+    pub fn new(value: T) -> Self {
+        if size_of::<T>() <= size_of::<usize>() {
+            let mut bytes = [0; size_of::<usize>()];
+            unsafe {
+                bytes
+                    .as_mut_ptr()
+                    .add(Self::OFFSET)
+                    .cast::<T>()
+                    .write_unaligned(value)
+            };
+            let word = usize::from_ne_bytes(bytes);
+            if word >> (usize::BITS - u8::BITS) == 0 {
+                let addr = (word << u8::BITS) | INLINE;
+                return Self(ptr::without_provenance(addr), PhantomData);
+            }
+        }
+
+        let ptr = Box::into_raw(Box::new(Boxed(value)));
+        Self(ptr.cast_const().cast(), PhantomData)
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.0.addr() & INLINE != 0
+    }
+
+    pub fn get(&self) -> T {
+        if self.is_inline() {
+            let bytes = (self.0.addr() >> u8::BITS).to_ne_bytes();
+            unsafe {
+                bytes
+                    .as_ptr()
+                    .add(Self::OFFSET)
+                    .cast::<T>()
+                    .read_unaligned()
+            }
+        } else {
+            unsafe { (*self.0.cast::<Boxed<T>>()).0 }
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.get()
+    }
+}
+
+unsafe impl<T: Inline + Send> Send for InlineOrBox<T> {}
+unsafe impl<T: Inline + Sync> Sync for InlineOrBox<T> {}
+
+impl<T: Inline> Drop for InlineOrBox<T> {
+    fn drop(&mut self) {
+        if !self.is_inline() {
+            drop(unsafe { Box::from_raw(self.0.cast_mut().cast::<Boxed<T>>()) });
+        }
+    }
+}
+
+impl<T: Inline> Clone for InlineOrBox<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
+}
+
+impl<T: Inline + Default> Default for InlineOrBox<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Inline> From<T> for InlineOrBox<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Inline + Debug> Debug for InlineOrBox<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("InlineOrBox").field(&self.get()).finish()
+    }
+}
+
+impl<T: Inline + PartialEq> PartialEq for InlineOrBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Inline + Eq> Eq for InlineOrBox<T> {}
+
+impl<T: Inline + Hash> Hash for InlineOrBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+/// Like [`SmallStr`](crate::small_str::SmallStr), the bits below the inline flag are free.
+unsafe impl<T: Inline> Pointer for InlineOrBox<T> {
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = INLINE;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
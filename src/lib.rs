@@ -1,18 +1,25 @@
 #![feature(associated_const_equality)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![feature(doc_cfg)]
 #![feature(ptr_mask)]
+#![feature(ptr_metadata)]
 #![feature(strict_provenance)]
+#![feature(unsize)]
 #![warn(unsafe_op_in_unsafe_fn)]
 #![no_std]
 #![doc = include_str!("../README.md")]
 
 use core::{
     borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
     marker::PhantomData,
     mem,
     mem::{align_of, ManuallyDrop},
-    num::NonZeroUsize,
-    ops::{Deref, DerefMut},
+    num::{NonZeroU16, NonZeroU32, NonZeroU8, NonZeroUsize},
+    ops::{BitAnd, BitOr, Deref, DerefMut},
+    pin::Pin,
     ptr,
 };
 
@@ -20,12 +27,57 @@ use core::{
 extern crate alloc;
 
 pub mod compact;
+pub mod one_of;
 pub mod shy_atomic;
+pub mod small_int;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod aligned_box;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod compact_value;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod inline_or_box;
+
+#[cfg(target_pointer_width = "64")]
+#[doc(cfg(target_pointer_width = "64"))]
+pub mod nan_box;
+
+#[cfg(all(feature = "alloc", target_pointer_width = "64"))]
+#[doc(cfg(all(feature = "alloc", target_pointer_width = "64")))]
+pub mod packed_slice;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod small_str;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod strong_or_weak;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod thin_box;
+
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+pub mod thin_rc;
+
+#[doc(hidden)]
+pub mod macros;
 
 #[cfg(feature = "alloc")]
 #[doc(cfg(feature = "alloc"))]
 mod impl_alloc;
 
+#[cfg(feature = "allocator_api")]
+#[doc(cfg(feature = "allocator_api"))]
+mod impl_allocator_api;
+
 #[cfg(feature = "triomphe")]
 #[doc(cfg(feature = "triomphe"))]
 mod impl_triomphe;
@@ -35,6 +87,8 @@ pub unsafe trait Pointer: Sized {
     const NON_NULL: bool = false;
     const ALIGNMENT: usize = 1;
     const CLONE_IN_PLACE: bool = false;
+    /// Number of most significant bits which are always zero.
+    const HIGH_BITS: u32 = 0;
 
     fn into_ptr(value: Self) -> *const ();
     unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self>;
@@ -153,10 +207,11 @@ unsafe impl<T> Pointer for ptr::NonNull<T> {
 }
 
 
-unsafe impl<T> Pointer for &'static T {
+unsafe impl<T> Pointer for &T {
     const NON_NULL: bool = true;
     const ALIGNMENT: usize = align_of::<T>();
     const CLONE_IN_PLACE: bool = true;
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         ptr::from_ref(value).cast()
@@ -167,9 +222,94 @@ unsafe impl<T> Pointer for &'static T {
     }
 }
 
+
+/// A mutable borrow kept as a raw pointer.
+///
+/// Use this instead of `&mut T`, which can't be a [`Pointer`]: every [`Ref`](compact::Ref) would
+/// hold its own `&mut T`. Shared access only creates `&T` here.
+pub struct MutRef<'a, T>(ptr::NonNull<T>, PhantomData<&'a mut T>);
+
+impl<'a, T> MutRef<'a, T> {
+    pub fn new(value: &'a mut T) -> Self {
+        Self(ptr::NonNull::from(value), PhantomData)
+    }
+
+    pub fn into_mut(self) -> &'a mut T {
+        unsafe { &mut *self.0.as_ptr() }
+    }
+}
+
+unsafe impl<T: Send> Send for MutRef<'_, T> {}
+unsafe impl<T: Sync> Sync for MutRef<'_, T> {}
+
+impl<T> Deref for MutRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<T> DerefMut for MutRef<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.0.as_mut() }
+    }
+}
+
+impl<'a, T> From<&'a mut T> for MutRef<'a, T> {
+    fn from(value: &'a mut T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MutRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for MutRef<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+unsafe impl<T> Pointer for MutRef<'_, T> {
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = align_of::<T>();
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        value.0.as_ptr().cast_const().cast()
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        let ptr = unsafe { ptr::NonNull::new_unchecked(ptr.cast_mut().cast()) };
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
+
+
+/// Encoding never moves the pointee, only the pointer itself.
+unsafe impl<P: Pointer + Deref> Pointer for Pin<P> {
+    const NON_NULL: bool = P::NON_NULL;
+    const ALIGNMENT: usize = P::ALIGNMENT;
+    const CLONE_IN_PLACE: bool = P::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = P::HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        P::into_ptr(unsafe { Pin::into_inner_unchecked(value) })
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        unsafe { P::from_ptr(ptr).map(|p| Pin::new_unchecked(p)) }
+    }
+}
+
 unsafe impl<T: Pointer<NON_NULL = true>> Pointer for Option<T> {
     const ALIGNMENT: usize = T::ALIGNMENT;
     const CLONE_IN_PLACE: bool = T::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = T::HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         match value {
@@ -196,6 +336,7 @@ where
     const NON_NULL: bool = T::NON_NULL;
     const ALIGNMENT: usize = min(T::ALIGNMENT, E::ALIGNMENT) >> 1;
     const CLONE_IN_PLACE: bool = T::CLONE_IN_PLACE && E::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = macros::high_bits(&[T::HIGH_BITS, E::HIGH_BITS]);
 
     fn into_ptr(value: Self) -> *const () {
         let (ptr, tag) = match value {
@@ -246,6 +387,85 @@ unsafe impl Pointer for NonZeroUsize {
 }
 
 
+macro_rules! inline_int {
+    ($($ty:ty as $unsigned:ty),+ $(,)?) => {$(
+        unsafe impl Pointer for $ty {
+            const ALIGNMENT: usize = 1 << (usize::BITS - <$ty>::BITS);
+            const CLONE_IN_PLACE: bool = true;
+
+            fn into_ptr(value: Self) -> *const () {
+                let shift = usize::BITS - <$ty>::BITS;
+                ptr::without_provenance((value as $unsigned as usize) << shift)
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+                MaybeOwned::new((ptr.addr() >> (usize::BITS - <$ty>::BITS)) as $ty)
+            }
+        }
+    )+};
+}
+
+macro_rules! inline_non_zero {
+    ($($ty:ty as $int:ty),+ $(,)?) => {$(
+        unsafe impl Pointer for $ty {
+            const NON_NULL: bool = true;
+            const ALIGNMENT: usize = <$int as Pointer>::ALIGNMENT;
+            const CLONE_IN_PLACE: bool = true;
+
+            fn into_ptr(value: Self) -> *const () {
+                <$int as Pointer>::into_ptr(value.get())
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+                let value = unsafe { <$int as Pointer>::from_ptr(ptr) };
+                MaybeOwned::new(unsafe { <$ty>::new_unchecked(*value) })
+            }
+        }
+    )+};
+}
+
+inline_int!(u8 as u8, u16 as u16, u32 as u32, i8 as u8, i16 as u16, i32 as u32);
+inline_non_zero!(NonZeroU8 as u8, NonZeroU16 as u16, NonZeroU32 as u32);
+
+#[cfg(target_pointer_width = "64")]
+inline_int!(u64 as u64, i64 as u64);
+
+#[cfg(target_pointer_width = "64")]
+inline_non_zero!(core::num::NonZeroU64 as u64);
+
+
+/// All code points fit in 21 bits.
+const CHAR_SHIFT: u32 = usize::BITS - 21;
+
+unsafe impl Pointer for char {
+    const ALIGNMENT: usize = 1 << CHAR_SHIFT;
+    const CLONE_IN_PLACE: bool = true;
+
+    fn into_ptr(value: Self) -> *const () {
+        ptr::without_provenance((value as usize) << CHAR_SHIFT)
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        let value = (ptr.addr() >> CHAR_SHIFT) as u32;
+        MaybeOwned::new(unsafe { char::from_u32_unchecked(value) })
+    }
+}
+
+
+unsafe impl Pointer for bool {
+    const ALIGNMENT: usize = 1 << (usize::BITS - 1);
+    const CLONE_IN_PLACE: bool = true;
+
+    fn into_ptr(value: Self) -> *const () {
+        ptr::without_provenance((value as usize) << (usize::BITS - 1))
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(ptr.addr() != 0)
+    }
+}
+
+
 unsafe impl Pointer for () {
     const NON_NULL: bool = true;
     const ALIGNMENT: usize = 1 << (usize::BITS - 1);
@@ -269,6 +489,27 @@ impl<const N: u32> Eval for FitsInUsize<N> {
     const RESULT: bool = N <= usize::BITS;
 }
 
+/// A predicate checking if [`usize`] has more than `N` bits.
+pub struct ShorterThanUsize<const N: u32>;
+
+impl<const N: u32> Eval for ShorterThanUsize<N> {
+    const RESULT: bool = N < usize::BITS;
+}
+
+/// A predicate checking if `N` is not zero.
+pub struct NotZero<const N: u32>;
+
+impl<const N: u32> Eval for NotZero<N> {
+    const RESULT: bool = N != 0;
+}
+
+/// A predicate checking if `T` is a zero-sized type.
+pub struct ZeroSized<T>(PhantomData<T>);
+
+impl<T> Eval for ZeroSized<T> {
+    const RESULT: bool = mem::size_of::<T>() == 0;
+}
+
 
 /// Unsigned integers at most `N` bits long.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -316,35 +557,17 @@ where
 
 
 /// A predicate checking if `P` is aligned enough to fit `N` bits.
+///
+/// **Breaking:** `(P, Bits<N>)` is now a [`Pointer`] when `FreeTagBits<P, (Bits<N>,)>` holds. A
+/// bound on this predicate no longer implies it, so generic code has to switch bounds.
+#[deprecated(note = "no longer implies `(P, Bits<N>): Pointer`, use `FreeTagBits<P, (Bits<N>,)>`")]
 pub struct FreeBits<P, const N: u32>(PhantomData<P>);
 
+#[allow(deprecated)]
 impl<P: Pointer, const N: u32> Eval for FreeBits<P, N> {
     const RESULT: bool = P::ALIGNMENT >= (1 << N);
 }
 
-unsafe impl<P, const N: u32> Pointer for (P, Bits<N>)
-where
-    P: Pointer,
-    FitsInUsize<N>: Eval<RESULT = true>,
-    FreeBits<P, N>: Eval<RESULT = true>,
-{
-    const NON_NULL: bool = P::NON_NULL;
-    const ALIGNMENT: usize = P::ALIGNMENT >> N;
-    const CLONE_IN_PLACE: bool = P::CLONE_IN_PLACE;
-
-    fn into_ptr(value: Self) -> *const () {
-        let ptr = P::into_ptr(value.0);
-        let tag = value.1.value() << Self::ALIGNMENT.trailing_zeros();
-        ptr.map_addr(|a| a | tag)
-    }
-
-    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
-        let tag = Bits::new_masked(ptr.addr() >> Self::ALIGNMENT.trailing_zeros());
-        let ptr = ptr.mask(!(P::ALIGNMENT - 1));
-        unsafe { P::from_ptr(ptr).map(|p| (p, tag)) }
-    }
-}
-
 
 /// Unsigned integer, always less than `N`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -394,31 +617,430 @@ unsafe impl<const N: usize> Pointer for Num<N> {
 
 
 /// A predicate checking if `P` is aligned enough to fit an unsigned int less than `N`.
+///
+/// **Breaking:** `(P, Num<N>)` is now a [`Pointer`] when `FreeTagBits<P, (Num<N>,)>` holds. A
+/// bound on this predicate no longer implies it, so generic code has to switch bounds.
+#[deprecated(note = "no longer implies `(P, Num<N>): Pointer`, use `FreeTagBits<P, (Num<N>,)>`")]
 pub struct CanFitNum<P, const N: usize>(PhantomData<P>);
 
+#[allow(deprecated)]
 impl<P: Pointer, const N: usize> Eval for CanFitNum<P, N> {
     const RESULT: bool = P::ALIGNMENT >= N;
 }
 
-unsafe impl<P, const N: usize> Pointer for (P, Num<N>)
+
+/// Small values which can be stored in the spare bits of a pointer.
+///
+/// Values are mapped to the dense range `0 .. COUNT`.
+pub unsafe trait Tag: Copy {
+    const COUNT: usize;
+    const BITS: u32 = Self::COUNT.next_power_of_two().trailing_zeros();
+
+    fn into_index(value: Self) -> usize;
+    unsafe fn from_index(index: usize) -> Self;
+}
+
+unsafe impl<const N: u32> Tag for Bits<N>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    ShorterThanUsize<N>: Eval<RESULT = true>,
+{
+    const COUNT: usize = 1 << N;
+    const BITS: u32 = N;
+
+    fn into_index(value: Self) -> usize {
+        value.value()
+    }
+
+    unsafe fn from_index(index: usize) -> Self {
+        Self(index)
+    }
+}
+
+unsafe impl<const N: usize> Tag for Num<N> {
+    const COUNT: usize = N;
+
+    fn into_index(value: Self) -> usize {
+        value.value()
+    }
+
+    unsafe fn from_index(index: usize) -> Self {
+        unsafe { Self::new_unchecked(index) }
+    }
+}
+
+unsafe impl Tag for bool {
+    const COUNT: usize = 2;
+
+    fn into_index(value: Self) -> usize {
+        value as usize
+    }
+
+    unsafe fn from_index(index: usize) -> Self {
+        index != 0
+    }
+}
+
+unsafe impl Tag for Ordering {
+    const COUNT: usize = 3;
+
+    fn into_index(value: Self) -> usize {
+        (value as i8 + 1) as usize
+    }
+
+    unsafe fn from_index(index: usize) -> Self {
+        match index {
+            0 => Ordering::Less,
+            1 => Ordering::Equal,
+            _ => Ordering::Greater,
+        }
+    }
+}
+
+unsafe impl Pointer for Ordering {
+    const ALIGNMENT: usize = Num::<3>::ALIGNMENT;
+    const CLONE_IN_PLACE: bool = true;
+
+    fn into_ptr(value: Self) -> *const () {
+        Num::<3>::into_ptr(unsafe { Num::new_unchecked(Tag::into_index(value)) })
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        unsafe { Num::<3>::from_ptr(ptr).map(|num| Tag::from_index(num.value())) }
+    }
+}
+
+
+/// A set of [`Tag`] values, one bit per value.
+///
+/// A tag with more values than bits in a `usize` is rejected:
+///
+/// ```compile_fail,E0080
+/// # use viaptr::{Flags, Num};
+/// let _ = Flags::<Num<{ usize::BITS as usize + 1 }>>::MASK;
+/// ```
+pub struct Flags<F>(usize, PhantomData<F>);
+
+impl<F: Tag> Flags<F> {
+    pub const MASK: usize = {
+        assert!(F::COUNT <= usize::BITS as usize, "too many flags");
+
+        match F::COUNT {
+            0 => 0,
+            n => usize::MAX >> (usize::BITS as usize - n),
+        }
+    };
+
+    pub const fn empty() -> Self {
+        Self(0, PhantomData)
+    }
+
+    pub const fn all() -> Self {
+        Self(Self::MASK, PhantomData)
+    }
+
+    pub const fn from_bits(bits: usize) -> Option<Self> {
+        if bits & Self::MASK != bits {
+            None
+        } else {
+            Some(Self(bits, PhantomData))
+        }
+    }
+
+    pub const fn from_bits_truncate(bits: usize) -> Self {
+        Self(bits & Self::MASK, PhantomData)
+    }
+
+    pub const fn bits(self) -> usize {
+        self.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, flag: F) -> bool {
+        self.0 & Self::bit(flag) != 0
+    }
+
+    pub fn set(&mut self, flag: F) {
+        self.0 |= Self::bit(flag);
+    }
+
+    pub fn clear(&mut self, flag: F) {
+        self.0 &= !Self::bit(flag);
+    }
+
+    pub fn toggle(&mut self, flag: F) {
+        self.0 ^= Self::bit(flag);
+    }
+
+    pub fn with(mut self, flag: F) -> Self {
+        self.set(flag);
+        self
+    }
+
+    pub fn without(mut self, flag: F) -> Self {
+        self.clear(flag);
+        self
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = F> {
+        (0 .. F::COUNT)
+            .filter(move |i| self.0 & (1 << i) != 0)
+            .map(|i| unsafe { F::from_index(i) })
+    }
+
+    fn bit(flag: F) -> usize {
+        1 << F::into_index(flag)
+    }
+}
+
+impl<F: Tag> From<F> for Flags<F> {
+    fn from(value: F) -> Self {
+        Self::empty().with(value)
+    }
+}
+
+impl<F: Tag> FromIterator<F> for Flags<F> {
+    fn from_iter<I: IntoIterator<Item = F>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::empty(), Self::with)
+    }
+}
+
+impl<F: Tag> BitOr<F> for Flags<F> {
+    type Output = Self;
+
+    fn bitor(self, rhs: F) -> Self {
+        self.with(rhs)
+    }
+}
+
+impl<F: Tag> BitOr for Flags<F> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0, PhantomData)
+    }
+}
+
+impl<F: Tag> BitAnd for Flags<F> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0, PhantomData)
+    }
+}
+
+impl<F> Clone for Flags<F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F> Copy for Flags<F> {}
+
+impl<F: Tag> Default for Flags<F> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<F> PartialEq for Flags<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<F> Eq for Flags<F> {}
+
+impl<F> Hash for Flags<F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<F: Tag + fmt::Debug> fmt::Debug for Flags<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+unsafe impl<F: Tag> Tag for Flags<F> {
+    const COUNT: usize = {
+        assert!(
+            F::COUNT < usize::BITS as usize,
+            "too many flags to fit in a pointer"
+        );
+        Self::MASK + 1
+    };
+    const BITS: u32 = F::COUNT as u32;
+
+    fn into_index(value: Self) -> usize {
+        value.0
+    }
+
+    unsafe fn from_index(index: usize) -> Self {
+        Self(index, PhantomData)
+    }
+}
+
+
+/// The position of a [`Tag`] in the spare bits of `P`.
+pub struct TagField<P, T>(u32, PhantomData<fn(P) -> T>);
+
+impl<P: Pointer, T: Tag> TagField<P, T> {
+    /// Create a field starting at bit `shift`, which must lie within `P`'s spare bits.
+    pub const unsafe fn new(shift: u32) -> Self {
+        Self(shift, PhantomData)
+    }
+
+    pub const fn shift(self) -> u32 {
+        self.0
+    }
+
+    pub unsafe fn get(self, ptr: *const ()) -> T {
+        let mask = (1 << T::BITS) - 1;
+        unsafe { T::from_index((ptr.addr() >> self.0) & mask) }
+    }
+
+    pub fn set(self, ptr: *const (), value: T) -> *const () {
+        let mask = ((1 << T::BITS) - 1) << self.0;
+        let tag = T::into_index(value) << self.0;
+        ptr.map_addr(|a| (a & !mask) | tag)
+    }
+}
+
+impl<P, T> Clone for TagField<P, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, T> Copy for TagField<P, T> {}
+
+
+/// A predicate checking if `P` is aligned enough to fit all the [`Tag`]s in the tuple `T`.
+///
+/// This is the bound on `(P, A, ..)` tagged pointers, replacing the deprecated [`FreeBits`] and
+/// [`CanFitNum`] bounds of `(P, Bits<N>)` and `(P, Num<N>)`.
+pub struct FreeTagBits<P, T>(PhantomData<(P, T)>);
+
+macro_rules! tagged {
+    ($($tag:ident $value:ident),+) => {
+        impl<P: Pointer, $($tag: Tag),+> Eval for FreeTagBits<P, ($($tag,)+)> {
+            const RESULT: bool = P::ALIGNMENT.trailing_zeros() >= 0 $(+ $tag::BITS)+;
+        }
+
+        /// Tags are stored right below the alignment of `P`, in order.
+        unsafe impl<P, $($tag),+> Pointer for (P, $($tag),+)
+        where
+            P: Pointer,
+            $($tag: Tag,)+
+            FreeTagBits<P, ($($tag,)+)>: Eval<RESULT = true>,
+        {
+            const NON_NULL: bool = P::NON_NULL;
+            const ALIGNMENT: usize = P::ALIGNMENT >> (0 $(+ $tag::BITS)+);
+            const CLONE_IN_PLACE: bool = P::CLONE_IN_PLACE;
+            const HIGH_BITS: u32 = P::HIGH_BITS;
+
+            fn into_ptr(value: Self) -> *const () {
+                let (ptr, $($value),+) = value;
+                let mut ptr = P::into_ptr(ptr);
+                let mut shift = P::ALIGNMENT.trailing_zeros();
+
+                $(
+                    shift -= $tag::BITS;
+                    ptr = unsafe { TagField::<Self, $tag>::new(shift) }.set(ptr, $value);
+                )+
+
+                ptr
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+                let mut shift = P::ALIGNMENT.trailing_zeros();
+
+                $(
+                    shift -= $tag::BITS;
+                    let $value = unsafe { TagField::<Self, $tag>::new(shift).get(ptr) };
+                )+
+
+                let ptr = ptr.mask(!(P::ALIGNMENT - 1));
+                unsafe { P::from_ptr(ptr).map(|p| (p, $($value),+)) }
+            }
+        }
+    };
+}
+
+tagged!(A a);
+tagged!(A a, B b);
+tagged!(A a, B b, C c);
+
+
+/// Unsigned integers at most `N` bits long, stored in the unused high bits of a pointer.
+#[cfg(target_pointer_width = "64")]
+#[doc(cfg(target_pointer_width = "64"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HighBits<const N: u32>(usize);
+
+#[cfg(target_pointer_width = "64")]
+impl<const N: u32> HighBits<N>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+{
+    pub const MASK: usize = (1 << N) - 1;
+
+    pub const fn new(value: usize) -> Option<Self> {
+        if value & Self::MASK != value {
+            None
+        } else {
+            Some(Self(value))
+        }
+    }
+
+    pub const fn new_masked(value: usize) -> Self {
+        Self(value & Self::MASK)
+    }
+
+    pub const fn value(self) -> usize {
+        self.0
+    }
+}
+
+/// A predicate checking if `P` has at least `N` unused high bits.
+#[cfg(target_pointer_width = "64")]
+#[doc(cfg(target_pointer_width = "64"))]
+pub struct FreeHighBits<P, const N: u32>(PhantomData<P>);
+
+#[cfg(target_pointer_width = "64")]
+impl<P: Pointer, const N: u32> Eval for FreeHighBits<P, N> {
+    const RESULT: bool = P::HIGH_BITS >= N;
+}
+
+/// The tag is stored right below the high bits still left unused by `P`.
+#[cfg(target_pointer_width = "64")]
+unsafe impl<P, const N: u32> Pointer for (P, HighBits<N>)
 where
     P: Pointer,
-    CanFitNum<P, N>: Eval<RESULT = true>,
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+    FreeHighBits<P, N>: Eval<RESULT = true>,
 {
     const NON_NULL: bool = P::NON_NULL;
-    const ALIGNMENT: usize = P::ALIGNMENT / N.next_power_of_two();
+    const ALIGNMENT: usize = P::ALIGNMENT;
     const CLONE_IN_PLACE: bool = P::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = P::HIGH_BITS - N;
 
     fn into_ptr(value: Self) -> *const () {
-        let ptr = P::into_ptr(value.0);
-        let tag = value.1.value() << Self::ALIGNMENT.trailing_zeros();
-        ptr.map_addr(|a| a | tag)
+        let (ptr, tag) = value;
+        let shift = usize::BITS - P::HIGH_BITS;
+        P::into_ptr(ptr).map_addr(|a| a | (tag.0 << shift))
     }
 
     unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
-        let tag = (ptr.addr() >> Self::ALIGNMENT.trailing_zeros()) & Num::<N>::MASK;
-        let tag = unsafe { Num::new_unchecked(tag) };
-        let ptr = ptr.mask(!(P::ALIGNMENT - 1));
+        let shift = usize::BITS - P::HIGH_BITS;
+        let tag = HighBits((ptr.addr() >> shift) & HighBits::<N>::MASK);
+        let ptr = ptr.mask(!(HighBits::<N>::MASK << shift));
         unsafe { P::from_ptr(ptr).map(|p| (p, tag)) }
     }
 }
@@ -467,6 +1089,7 @@ where
     const NON_NULL: bool = T::NON_NULL;
     const ALIGNMENT: usize = T::ALIGNMENT >> 1;
     const CLONE_IN_PLACE: bool = T::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = T::HIGH_BITS;
 
     fn into_ptr(value: Self) -> *const () {
         match value.into() {
@@ -507,6 +1130,27 @@ unsafe impl Pointer for Null {
 }
 
 
+/// High bits which are zero in every user-space address.
+///
+/// Linux on x86-64 keeps user space below 2^47 unless a larger address is explicitly requested
+/// from `mmap`, even with 5-level paging. Other targets make no such promise, e.g. aarch64 can
+/// map 52-bit addresses and kernel addresses live in the upper half.
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub(crate) const ADDRESS_HIGH_BITS: u32 = 16;
+
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+pub(crate) const ADDRESS_HIGH_BITS: u32 = 0;
+
+/// Flag set in the tag byte of words which hold their data inline instead of a pointer.
+pub(crate) const INLINE: usize = 0b100;
+
+/// Memory offset of the least significant byte, which holds the inline flag.
+#[cfg(target_endian = "little")]
+pub(crate) const TAG_BYTE: usize = 0;
+
+#[cfg(target_endian = "big")]
+pub(crate) const TAG_BYTE: usize = mem::size_of::<usize>() - 1;
+
 pub(crate) const fn min(x: usize, y: usize) -> usize {
     if x < y {
         x
@@ -0,0 +1,533 @@
+//! Support code for the exported macros and [`OneOf`](crate::one_of::OneOf).
+
+#[cfg(feature = "alloc")]
+pub use alloc::boxed::Box;
+
+use crate::max;
+
+
+/// Prefix code tags for the variants of a sum type with at most `N` variants.
+///
+/// Every variant gets a code no longer than its spare bits allow, so variants with low alignment
+/// get short codes and highly aligned ones get long codes. The codes are read starting from the
+/// least significant bit, right above the alignment left over for outer types.
+#[derive(Debug, Clone, Copy)]
+pub struct SumLayout<const N: usize> {
+    /// Alignment left over for outer types.
+    pub align: usize,
+    /// Index of the variant tagged with all zeros.
+    pub zero: usize,
+    count: usize,
+    codes: [usize; N],
+    masks: [usize; N],
+}
+
+impl<const N: usize> SumLayout<N> {
+    pub const fn new(alignments: &[usize]) -> Self {
+        let count = alignments.len();
+        assert!(count <= N, "too many variants");
+
+        let mut max_bits = 0;
+        let mut i = 0;
+
+        while i < count {
+            max_bits = max(max_bits, alignments[i].trailing_zeros() as usize);
+            i += 1;
+        }
+
+        // Kraft's inequality, scaled by `2^max_bits`.
+        let mut total = 0u128;
+        i = 0;
+
+        while i < count {
+            total += 1 << (max_bits - alignments[i].trailing_zeros() as usize);
+            i += 1;
+        }
+
+        let needed = total.next_power_of_two().trailing_zeros() as usize;
+        assert!(
+            needed <= max_bits,
+            "variants are not aligned enough to fit the tag"
+        );
+        let shift = max_bits - needed;
+
+        // Canonical prefix code, shortest codes first.
+        let mut layout = Self {
+            align: 1 << shift,
+            zero: 0,
+            count,
+            codes: [0; N],
+            masks: [0; N],
+        };
+
+        let mut code = 0usize;
+        let mut prev_len = 0;
+        let mut len = 1;
+
+        while len <= max_bits {
+            i = 0;
+
+            while i < count {
+                if alignments[i].trailing_zeros() as usize - shift == len {
+                    code <<= len - prev_len;
+                    prev_len = len;
+
+                    if code == 0 {
+                        layout.zero = i;
+                    }
+
+                    let reversed = code.reverse_bits() >> (usize::BITS as usize - len);
+                    layout.codes[i] = reversed << shift;
+                    layout.masks[i] = ((1 << len) - 1) << shift;
+                    code += 1;
+                }
+
+                i += 1;
+            }
+
+            len += 1;
+        }
+
+        layout
+    }
+
+    /// Store the code of variant `index` in the spare bits of `ptr`.
+    pub fn into_ptr(&self, ptr: *const (), index: usize) -> *const () {
+        ptr.map_addr(|a| a | self.codes[index])
+    }
+
+    /// Split a pointer produced by [`SumLayout::into_ptr`] into the variant pointer and index.
+    pub fn from_ptr(&self, ptr: *const ()) -> (*const (), usize) {
+        let mut index = 0;
+
+        while index < self.count - 1 && ptr.addr() & self.masks[index] != self.codes[index] {
+            index += 1;
+        }
+
+        (ptr.mask(!(self.masks[index] | (self.align - 1))), index)
+    }
+}
+
+
+/// Define an enum holding one [`Pointer`](crate::Pointer) per variant and implement
+/// [`Pointer`](crate::Pointer) for it.
+///
+/// The variant is identified by a prefix code stored in the spare low bits, shorter for variants
+/// with lower alignment.
+///
+/// ```rust,ignore
+/// pointer_enum! {
+///     #[derive(Debug, Clone)]
+///     pub enum Node {
+///         Leaf(Box<Leaf>),
+///         Branch(Arc<Branch>),
+///         Shared(&'static Branch),
+///         Empty(Null),
+///     }
+/// }
+///
+/// let node = Compact::new(Node::Empty(Null));
+/// ```
+#[macro_export]
+macro_rules! pointer_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$var_attr:meta])* $var:ident($ty:ty)),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name {
+            $($(#[$var_attr])* $var($ty),)+
+        }
+
+        $crate::pointer_enum!(@impl $name [$($var($ty))+]);
+    };
+
+    (@impl $name:ident [$first_var:ident($first:ty) $($var:ident($ty:ty))*]) => {
+        impl $name {
+            const __LAYOUT: $crate::macros::SumLayout<
+                { [::core::stringify!($first_var) $(, ::core::stringify!($var))*].len() },
+            > = $crate::macros::SumLayout::new(&[
+                <$first as $crate::Pointer>::ALIGNMENT,
+                $(<$ty as $crate::Pointer>::ALIGNMENT,)*
+            ]);
+        }
+
+        unsafe impl $crate::Pointer for $name {
+            const ALIGNMENT: usize = Self::__LAYOUT.align;
+
+            const NON_NULL: bool = [
+                <$first as $crate::Pointer>::NON_NULL,
+                $(<$ty as $crate::Pointer>::NON_NULL,)*
+            ][Self::__LAYOUT.zero];
+
+            const CLONE_IN_PLACE: bool = <$first as $crate::Pointer>::CLONE_IN_PLACE
+                $(&& <$ty as $crate::Pointer>::CLONE_IN_PLACE)*;
+
+            const HIGH_BITS: u32 = $crate::macros::high_bits(&[
+                <$first as $crate::Pointer>::HIGH_BITS,
+                $(<$ty as $crate::Pointer>::HIGH_BITS,)*
+            ]);
+
+            fn into_ptr(value: Self) -> *const () {
+                enum __Variant { $first_var, $($var,)* }
+
+                let (ptr, index) = match value {
+                    $name::$first_var(x) => {
+                        (<$first as $crate::Pointer>::into_ptr(x), __Variant::$first_var)
+                    },
+                    $($name::$var(x) => (<$ty as $crate::Pointer>::into_ptr(x), __Variant::$var),)*
+                };
+
+                Self::__LAYOUT.into_ptr(ptr, index as usize)
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> $crate::MaybeOwned<Self> {
+                enum __Variant { $first_var, $($var,)* }
+
+                let (ptr, index) = Self::__LAYOUT.from_ptr(ptr);
+
+                $(if index == __Variant::$var as usize {
+                    return unsafe { <$ty as $crate::Pointer>::from_ptr(ptr).map($name::$var) };
+                })*
+
+                debug_assert!(index == __Variant::$first_var as usize);
+                unsafe { <$first as $crate::Pointer>::from_ptr(ptr).map($name::$first_var) }
+            }
+        }
+
+        const _: usize = <$name as $crate::Pointer>::ALIGNMENT;
+    };
+}
+
+
+/// Alignment left over after storing tags of the given sizes in the spare bits.
+pub const fn tag_alignment(align: usize, bits: &[u32]) -> usize {
+    let mut total = 0;
+    let mut i = 0;
+
+    while i < bits.len() {
+        total += bits[i];
+        i += 1;
+    }
+
+    assert!(
+        align.trailing_zeros() >= total,
+        "pointer is not aligned enough to fit the tags"
+    );
+    align >> total
+}
+
+/// High bits left unused by all of the variants.
+pub const fn high_bits(bits: &[u32]) -> u32 {
+    let mut min = u32::MAX;
+    let mut i = 0;
+
+    while i < bits.len() {
+        if bits[i] < min {
+            min = bits[i];
+        }
+        i += 1;
+    }
+
+    min
+}
+
+/// Clear the spare bits of a pointer with the given alignment.
+pub fn untag(ptr: *const (), align: usize) -> *const () {
+    ptr.mask(!(align - 1))
+}
+
+
+/// Define a struct holding a [`Pointer`](crate::Pointer) followed by [`Tag`](crate::Tag)s and
+/// implement [`Pointer`](crate::Pointer) for it.
+///
+/// The tags are packed into the spare low bits of the pointer, the first one right below the
+/// pointer's alignment. A [`TagField`](crate::TagField) constant named after each tag field gives
+/// access to it inside a [`Compact`](crate::compact::Compact) without decoding the pointer.
+///
+/// ```rust,ignore
+/// pointer_struct! {
+///     #[derive(Debug, Clone)]
+///     pub struct Link {
+///         pub node: Box<Node>,
+///         pub red: bool,
+///         pub state: Bits<2>,
+///         pub kind: Num<3>,
+///     }
+/// }
+///
+/// let mut link = Compact::new(Link { node, red: false, state: Bits::new_masked(1), kind });
+/// link.set_tag(Link::red, true);
+/// assert!(link.get_tag(Link::red));
+/// ```
+#[macro_export]
+macro_rules! pointer_struct {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $(#[$ptr_attr:meta])* $ptr_vis:vis $ptr:ident: $ptr_ty:ty,
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident: $ty:ty),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $(#[$ptr_attr])* $ptr_vis $ptr: $ptr_ty,
+            $($(#[$field_attr])* $field_vis $field: $ty,)+
+        }
+
+        #[allow(non_upper_case_globals)]
+        impl $name {
+            $crate::pointer_struct!(
+                @fields $name [<$ptr_ty as $crate::Pointer>::ALIGNMENT.trailing_zeros()]
+                $($field_vis $field: $ty,)+
+            );
+        }
+
+        unsafe impl $crate::Pointer for $name {
+            const NON_NULL: bool = <$ptr_ty as $crate::Pointer>::NON_NULL;
+            const CLONE_IN_PLACE: bool = <$ptr_ty as $crate::Pointer>::CLONE_IN_PLACE;
+            const HIGH_BITS: u32 = <$ptr_ty as $crate::Pointer>::HIGH_BITS;
+
+            const ALIGNMENT: usize = $crate::macros::tag_alignment(
+                <$ptr_ty as $crate::Pointer>::ALIGNMENT,
+                &[$(<$ty as $crate::Tag>::BITS),+],
+            );
+
+            fn into_ptr(value: Self) -> *const () {
+                let ptr = <$ptr_ty as $crate::Pointer>::into_ptr(value.$ptr);
+                $(let ptr = Self::$field.set(ptr, value.$field);)+
+                ptr
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> $crate::MaybeOwned<Self> {
+                $(let $field = unsafe { Self::$field.get(ptr) };)+
+                let ptr = $crate::macros::untag(ptr, <$ptr_ty as $crate::Pointer>::ALIGNMENT);
+                unsafe {
+                    <$ptr_ty as $crate::Pointer>::from_ptr(ptr)
+                        .map(|$ptr| Self { $ptr, $($field),+ })
+                }
+            }
+        }
+
+        const _: usize = <$name as $crate::Pointer>::ALIGNMENT;
+    };
+
+    (@fields $name:ident [$shift:expr] $vis:vis $field:ident: $ty:ty, $($rest:tt)*) => {
+        $vis const $field: $crate::TagField<$name, $ty> =
+            unsafe { $crate::TagField::new($shift - <$ty as $crate::Tag>::BITS) };
+
+        $crate::pointer_struct!(@fields $name [$shift - <$ty as $crate::Tag>::BITS] $($rest)*);
+    };
+
+    (@fields $name:ident [$shift:expr]) => {};
+}
+
+
+/// Define a fieldless enum and implement [`Tag`](crate::Tag) and [`Pointer`](crate::Pointer)
+/// for it.
+///
+/// Variants are numbered in order, explicit discriminants are not allowed. On its own the enum is
+/// encoded like [`Num`](crate::Num), and like any [`Tag`](crate::Tag) it can be attached to a
+/// pointer as `(P, Enum)`. The enum must be [`Copy`].
+///
+/// ```rust,ignore
+/// tag_enum! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///     pub enum Color {
+///         Red,
+///         Black,
+///     }
+/// }
+///
+/// let link = Compact::new((node, Color::Red));
+/// ```
+#[macro_export]
+macro_rules! tag_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$var_attr:meta])* $var:ident),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name {
+            $($(#[$var_attr])* $var,)+
+        }
+
+        unsafe impl $crate::Tag for $name {
+            const COUNT: usize = [$(Self::$var),+].len();
+
+            fn into_index(value: Self) -> usize {
+                value as usize
+            }
+
+            unsafe fn from_index(index: usize) -> Self {
+                [$(Self::$var),+][index]
+            }
+        }
+
+        unsafe impl $crate::Pointer for $name {
+            const ALIGNMENT: usize =
+                <$crate::Num<{ <$name as $crate::Tag>::COUNT }> as $crate::Pointer>::ALIGNMENT;
+
+            const CLONE_IN_PLACE: bool = true;
+
+            fn into_ptr(value: Self) -> *const () {
+                let index = <Self as $crate::Tag>::into_index(value);
+                let num = unsafe {
+                    $crate::Num::<{ <$name as $crate::Tag>::COUNT }>::new_unchecked(index)
+                };
+                $crate::Pointer::into_ptr(num)
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> $crate::MaybeOwned<Self> {
+                type Index = $crate::Num<{ <$name as $crate::Tag>::COUNT }>;
+                unsafe {
+                    <Index as $crate::Pointer>::from_ptr(ptr)
+                        .map(|num| <Self as $crate::Tag>::from_index(num.value()))
+                }
+            }
+        }
+    };
+}
+
+
+/// Define a one-word handle to any of a closed set of types implementing a trait, dispatching
+/// the listed trait methods by the tag bits instead of a vtable.
+///
+/// The handle is a [`pointer_enum!`] with a boxed variant per type. The trait is implemented for
+/// it as well as for [`Compact`](crate::compact::Compact) around it. Only methods taking `&self`
+/// or `&mut self` without generic parameters can be listed.
+///
+/// ```rust,ignore
+/// pointer_dispatch! {
+///     #[derive(Debug, Clone)]
+///     pub enum AnyShape: Shape {
+///         Circle(Circle),
+///         Square(Square),
+///     }
+///
+///     fn area(&self) -> f64;
+///     fn scale(&mut self, factor: f64);
+/// }
+///
+/// let mut shape = Compact::new(AnyShape::Circle(Box::new(Circle { radius: 1.0 })));
+/// shape.scale(2.0);
+/// ```
+#[cfg(feature = "alloc")]
+#[doc(cfg(feature = "alloc"))]
+#[macro_export]
+macro_rules! pointer_dispatch {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident: $trait:path {
+            $($(#[$var_attr:meta])* $var:ident($ty:ty)),+ $(,)?
+        }
+
+        $($methods:tt)*
+    ) => {
+        $crate::pointer_enum! {
+            $(#[$attr])*
+            $vis enum $name {
+                $($(#[$var_attr])* $var($crate::macros::Box<$ty>),)+
+            }
+        }
+
+        impl $trait for $name {
+            $crate::pointer_dispatch!(@methods [enum $name $trait [$($var($ty))+]] $($methods)*);
+        }
+
+        impl $trait for $crate::compact::Compact<$name> {
+            $crate::pointer_dispatch!(@methods [compact $name $trait [$($var($ty))+]] $($methods)*);
+        }
+    };
+
+    (
+        @methods $ctx:tt
+        fn $method:ident(&self $(, $arg:ident: $arg_ty:ty)* $(,)?) $(-> $ret:ty)?;
+        $($rest:tt)*
+    ) => {
+        $crate::pointer_dispatch!(
+            @method $ctx [ref] $method [$(, $arg: $arg_ty)*] ($($arg),*) [$(-> $ret)?]
+        );
+        $crate::pointer_dispatch!(@methods $ctx $($rest)*);
+    };
+
+    (
+        @methods $ctx:tt
+        fn $method:ident(&mut self $(, $arg:ident: $arg_ty:ty)* $(,)?) $(-> $ret:ty)?;
+        $($rest:tt)*
+    ) => {
+        $crate::pointer_dispatch!(
+            @method $ctx [mut] $method [$(, $arg: $arg_ty)*] ($($arg),*) [$(-> $ret)?]
+        );
+        $crate::pointer_dispatch!(@methods $ctx $($rest)*);
+    };
+
+    (@methods $ctx:tt) => {};
+
+    (
+        @method [enum $name:ident $trait:path [$($var:ident($ty:ty))+]]
+        [ref] $method:ident [$($param:tt)*] $args:tt [$($ret:tt)*]
+    ) => {
+        fn $method(&self $($param)*) $($ret)* {
+            match self {
+                $($name::$var(x) => {
+                    $crate::pointer_dispatch!(@call [<$ty as $trait>::$method] (&**x) $args)
+                },)+
+            }
+        }
+    };
+
+    (
+        @method [enum $name:ident $trait:path [$($var:ident($ty:ty))+]]
+        [mut] $method:ident [$($param:tt)*] $args:tt [$($ret:tt)*]
+    ) => {
+        fn $method(&mut self $($param)*) $($ret)* {
+            match self {
+                $($name::$var(x) => {
+                    $crate::pointer_dispatch!(@call [<$ty as $trait>::$method] (&mut **x) $args)
+                },)+
+            }
+        }
+    };
+
+    // The boxed values outlive the decoded enum, their references are valid as long as `self`.
+    (
+        @method [compact $name:ident $trait:path [$($var:ident($ty:ty))+]]
+        [ref] $method:ident [$($param:tt)*] $args:tt [$($ret:tt)*]
+    ) => {
+        fn $method(&self $($param)*) $($ret)* {
+            let value = $crate::compact::Compact::get_ref(self);
+
+            match &*value {
+                $($name::$var(x) => {
+                    let x = unsafe { &*::core::ptr::from_ref::<$ty>(x) };
+                    $crate::pointer_dispatch!(@call [<$ty as $trait>::$method] x $args)
+                },)+
+            }
+        }
+    };
+
+    (
+        @method [compact $name:ident $trait:path [$($var:ident($ty:ty))+]]
+        [mut] $method:ident [$($param:tt)*] $args:tt [$($ret:tt)*]
+    ) => {
+        fn $method(&mut self $($param)*) $($ret)* {
+            let mut value = $crate::compact::Compact::get_mut(self);
+
+            match &mut *value {
+                $($name::$var(x) => {
+                    let x = unsafe { &mut *::core::ptr::from_mut::<$ty>(x) };
+                    $crate::pointer_dispatch!(@call [<$ty as $trait>::$method] x $args)
+                },)+
+            }
+        }
+    };
+
+    (@call [$($f:tt)*] $recv:tt ($($arg:ident),*)) => {
+        $($f)*($recv $(, $arg)*)
+    };
+}
@@ -0,0 +1,152 @@
+use core::{fmt::Debug, marker::PhantomData, mem::ManuallyDrop};
+
+use crate::{compact::Ref, Eval, FreeHighBits, MaybeOwned, Pointer};
+
+
+/// Either an [`f64`] or a [`Pointer`] hidden in the payload of a NaN.
+///
+/// NaNs are canonicalized, so the negative quiet NaNs are free to hold `P`, which must leave the
+/// top 16 bits unused. This limits `P` in two ways:
+///
+/// - Heap pointers and references only leave them unused on x86-64 Linux, where addresses are
+///   known to fit in 48 bits. No pointer type can be stored on other targets.
+/// - Integer payloads such as [`Bits`](crate::Bits) or [`SmallInt`](crate::small_int::SmallInt)
+///   live in the high bits themselves and can't be stored at all.
+pub struct NanBox<P>(*const (), PhantomData<P>)
+where
+    P: Pointer,
+    FreeHighBits<P, 16>: Eval<RESULT = true>;
+
+const TAG: usize = 0xfff8 << 48;
+const TAG_MASK: usize = 0xffff << 48;
+const CANONICAL_NAN: u64 = 0x7ff8 << 48;
+
+impl<P> NanBox<P>
+where
+    P: Pointer,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    pub fn from_f64(value: f64) -> Self {
+        let bits = if value.is_nan() {
+            CANONICAL_NAN
+        } else {
+            value.to_bits()
+        };
+        Self(core::ptr::without_provenance(bits as usize), PhantomData)
+    }
+
+    pub fn from_pointer(value: P) -> Self {
+        Self(P::into_ptr(value).map_addr(|a| a | TAG), PhantomData)
+    }
+
+    pub fn is_f64(&self) -> bool {
+        self.0.addr() & TAG_MASK != TAG
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        if self.is_f64() {
+            Some(f64::from_bits(self.0.addr() as u64))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_pointer(&self) -> Option<Ref<P>> {
+        if self.is_f64() {
+            None
+        } else {
+            Some(unsafe { Ref::from_ptr(self.payload()) })
+        }
+    }
+
+    pub fn into_inner(self) -> Result<f64, P> {
+        let value = ManuallyDrop::new(self);
+
+        match value.as_f64() {
+            Some(x) => Ok(x),
+            None => Err(unsafe { P::from_ptr(value.payload()).assume_owned() }),
+        }
+    }
+
+    fn payload(&self) -> *const () {
+        self.0.mask(!TAG_MASK)
+    }
+}
+
+impl<P> Drop for NanBox<P>
+where
+    P: Pointer,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    fn drop(&mut self) {
+        if !self.is_f64() {
+            unsafe { P::from_ptr(self.payload()).assume_owned() };
+        }
+    }
+}
+
+impl<P> Clone for NanBox<P>
+where
+    P: Pointer + Clone,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    fn clone(&self) -> Self {
+        match self.as_pointer() {
+            Some(p) => Self::from_pointer(p.clone()),
+            None => Self(self.0, PhantomData),
+        }
+    }
+}
+
+impl<P> From<f64> for NanBox<P>
+where
+    P: Pointer,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    fn from(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl<P> Debug for NanBox<P>
+where
+    P: Pointer + Debug,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.as_pointer() {
+            Some(p) => f.debug_tuple("Pointer").field(&*p).finish(),
+            None => f.debug_tuple("F64").field(&self.as_f64().unwrap()).finish(),
+        }
+    }
+}
+
+impl<P> PartialEq for NanBox<P>
+where
+    P: Pointer + PartialEq,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self.as_pointer(), other.as_pointer()) {
+            (Some(x), Some(y)) => *x == *y,
+            (None, None) => self.as_f64() == other.as_f64(),
+            _ => false,
+        }
+    }
+}
+
+unsafe impl<P> Pointer for NanBox<P>
+where
+    P: Pointer,
+    FreeHighBits<P, 16>: Eval<RESULT = true>,
+{
+    const CLONE_IN_PLACE: bool = P::CLONE_IN_PLACE;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
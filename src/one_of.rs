@@ -0,0 +1,228 @@
+use core::{any::TypeId, fmt::Debug, marker::PhantomData, mem::ManuallyDrop, ops::Deref};
+
+use crate::{
+    compact::Ref,
+    macros::{high_bits, SumLayout},
+    MaybeOwned, Pointer,
+};
+
+
+/// One of the [`Pointer`] types in the tuple `L`.
+///
+/// Unlike nested [`Result`]s, the variant is identified by a single prefix code, which is shorter
+/// for variants with lower alignment. With equally aligned variants this takes
+/// `N.next_power_of_two().trailing_zeros()` bits.
+pub struct OneOf<L: Variants>(*const (), PhantomData<L>);
+
+impl<L: Variants> OneOf<L> {
+    unsafe fn from_parts(index: usize, ptr: *const ()) -> Self {
+        Self(L::LAYOUT.into_ptr(ptr, index), PhantomData)
+    }
+
+    fn parts(&self) -> (*const (), usize) {
+        L::LAYOUT.from_ptr(self.0)
+    }
+
+    /// Position of the current variant in `L`.
+    pub fn index(&self) -> usize {
+        self.parts().1
+    }
+
+    pub fn new<T: Pointer, I>(value: T) -> Self
+    where
+        L: Member<T, I>,
+    {
+        unsafe { Self::from_parts(L::INDEX, T::into_ptr(value)) }
+    }
+}
+
+impl<L: StaticVariants> OneOf<L> {
+    pub fn is<T: 'static>(&self) -> bool {
+        L::position(TypeId::of::<T>()) == Some(self.index())
+    }
+
+    pub fn get<T: Pointer + 'static>(&self) -> Option<Ref<T>> {
+        if self.is::<T>() {
+            Some(unsafe { Ref::from_ptr(self.parts().0) })
+        } else {
+            None
+        }
+    }
+
+    pub fn into<T: Pointer + 'static>(self) -> Result<T, Self> {
+        if self.is::<T>() {
+            let ptr = ManuallyDrop::new(self).parts().0;
+            Ok(unsafe { T::from_ptr(ptr).assume_owned() })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<L: Variants> Drop for OneOf<L> {
+    fn drop(&mut self) {
+        let (ptr, index) = self.parts();
+        unsafe { L::drop_variant(index, ptr) };
+    }
+}
+
+unsafe impl<L: Variants> Pointer for OneOf<L> {
+    const NON_NULL: bool = L::NON_NULL;
+    const ALIGNMENT: usize = L::LAYOUT.align;
+    const CLONE_IN_PLACE: bool = L::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = L::HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
+
+
+/// Number of types in the largest tuple implementing [`Variants`].
+const MAX_VARIANTS: usize = 8;
+
+/// Tuples of [`Pointer`] types which can be used with [`OneOf`].
+pub unsafe trait Variants {
+    const LAYOUT: SumLayout<MAX_VARIANTS>;
+    const NON_NULL: bool;
+    const CLONE_IN_PLACE: bool;
+    const HIGH_BITS: u32;
+
+    unsafe fn drop_variant(index: usize, ptr: *const ());
+}
+
+/// [`Variants`] which can be looked up by type.
+pub unsafe trait StaticVariants: Variants {
+    fn position(id: TypeId) -> Option<usize>;
+}
+
+/// [`Variants`] containing `T`, with `I` disambiguating its position.
+///
+/// # Safety
+///
+/// `INDEX` must be the position of `T` in the tuple.
+pub unsafe trait Member<T, I>: Variants {
+    const INDEX: usize;
+}
+
+/// Marks the position of a type in [`Variants`].
+pub struct Index<const N: usize>;
+
+macro_rules! members {
+    ([$($all:ident),+]) => {};
+    ([$($all:ident),+] $index:tt: $ty:ident $(, $rest_index:tt: $rest:ident)*) => {
+        unsafe impl<$($all: Pointer,)+> Member<$ty, Index<$index>> for ($($all,)+) {
+            const INDEX: usize = $index;
+        }
+
+        members!([$($all),+] $($rest_index: $rest),*);
+    };
+}
+
+macro_rules! variants {
+    ($first_index:tt: $first:ident $(, $index:tt: $ty:ident)+) => {
+        unsafe impl<$first: Pointer $(, $ty: Pointer)+> Variants for ($first, $($ty,)+) {
+            const LAYOUT: SumLayout<MAX_VARIANTS> =
+                SumLayout::new(&[$first::ALIGNMENT $(, $ty::ALIGNMENT)+]);
+            const NON_NULL: bool = [$first::NON_NULL $(, $ty::NON_NULL)+][Self::LAYOUT.zero];
+            const CLONE_IN_PLACE: bool = $first::CLONE_IN_PLACE $(&& $ty::CLONE_IN_PLACE)+;
+            const HIGH_BITS: u32 = high_bits(&[$first::HIGH_BITS $(, $ty::HIGH_BITS)+]);
+
+            unsafe fn drop_variant(index: usize, ptr: *const ()) {
+                match index {
+                    $($index => drop(unsafe { $ty::from_ptr(ptr).assume_owned() }),)+
+                    _ => drop(unsafe { $first::from_ptr(ptr).assume_owned() }),
+                }
+            }
+        }
+
+        members!([$first $(, $ty)+] $first_index: $first $(, $index: $ty)+);
+
+        unsafe impl<$first, $($ty,)+> StaticVariants for ($first, $($ty,)+)
+        where
+            $first: Pointer + 'static,
+            $($ty: Pointer + 'static,)+
+        {
+            fn position(id: TypeId) -> Option<usize> {
+                if id == TypeId::of::<$first>() {
+                    return Some($first_index);
+                }
+
+                $(if id == TypeId::of::<$ty>() {
+                    return Some($index);
+                })+
+
+                None
+            }
+        }
+
+        impl<$first, $($ty,)+> Clone for OneOf<($first, $($ty,)+)>
+        where
+            $first: Pointer + Clone,
+            $($ty: Pointer + Clone,)+
+        {
+            fn clone(&self) -> Self {
+                let (ptr, index) = self.parts();
+
+                let ptr = match index {
+                    $($index => $ty::into_ptr(unsafe { $ty::from_ptr(ptr) }.deref().clone()),)+
+                    _ => $first::into_ptr(unsafe { $first::from_ptr(ptr) }.deref().clone()),
+                };
+
+                unsafe { Self::from_parts(index, ptr) }
+            }
+        }
+
+        impl<$first, $($ty,)+> Debug for OneOf<($first, $($ty,)+)>
+        where
+            $first: Pointer + Debug,
+            $($ty: Pointer + Debug,)+
+        {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let (ptr, index) = self.parts();
+                let mut f = f.debug_tuple("OneOf");
+
+                match index {
+                    $($index => f.field(unsafe { $ty::from_ptr(ptr) }.deref()),)+
+                    _ => f.field(unsafe { $first::from_ptr(ptr) }.deref()),
+                };
+
+                f.finish()
+            }
+        }
+
+        impl<$first, $($ty,)+> PartialEq for OneOf<($first, $($ty,)+)>
+        where
+            $first: Pointer + PartialEq,
+            $($ty: Pointer + PartialEq,)+
+        {
+            fn eq(&self, other: &Self) -> bool {
+                let (x, index) = self.parts();
+                let (y, other_index) = other.parts();
+
+                if index != other_index {
+                    return false;
+                }
+
+                unsafe {
+                    match index {
+                        $($index => *$ty::from_ptr(x) == *$ty::from_ptr(y),)+
+                        _ => *$first::from_ptr(x) == *$first::from_ptr(y),
+                    }
+                }
+            }
+        }
+    };
+}
+
+variants!(0: A, 1: B);
+variants!(0: A, 1: B, 2: C);
+variants!(0: A, 1: B, 2: C, 3: D);
+variants!(0: A, 1: B, 2: C, 3: D, 4: E);
+variants!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+variants!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+variants!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
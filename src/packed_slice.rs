@@ -0,0 +1,173 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem::{align_of, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr,
+};
+
+use crate::{Eval, FreeHighBits, MaybeOwned, Pointer};
+
+
+/// Number of high bits taken up by the length.
+const LEN_BITS: u32 = 16;
+
+/// A boxed slice which is a single pointer wide, with the length in the unused high bits.
+///
+/// Only available if [`Box`] leaves at least 16 high bits unused.
+pub struct PackedSliceBox<T>(*const (), PhantomData<T>)
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>;
+
+impl<T> PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    pub const MAX_LEN: usize = (1 << LEN_BITS) - 1;
+    const LEN_SHIFT: u32 = usize::BITS - <Box<T>>::HIGH_BITS;
+
+    /// Returns back `value` if it is longer than [`Self::MAX_LEN`].
+    pub fn new(value: Box<[T]>) -> Result<Self, Box<[T]>> {
+        if value.len() > Self::MAX_LEN {
+            Err(value)
+        } else {
+            Ok(unsafe { Self::new_unchecked(value) })
+        }
+    }
+
+    /// The length of `value` must not exceed [`Self::MAX_LEN`].
+    pub unsafe fn new_unchecked(value: Box<[T]>) -> Self {
+        let len = value.len();
+        let ptr = Box::into_raw(value).cast::<()>().cast_const();
+        Self(ptr.map_addr(|a| a | (len << Self::LEN_SHIFT)), PhantomData)
+    }
+
+    pub fn into_box(self) -> Box<[T]> {
+        let value = ManuallyDrop::new(self);
+        unsafe { Box::from_raw(value.as_ptr()) }
+    }
+
+    fn as_ptr(&self) -> *mut [T] {
+        let len = self.0.addr() >> Self::LEN_SHIFT;
+        let ptr = self.0.mask(!(Self::MAX_LEN << Self::LEN_SHIFT));
+        ptr::slice_from_raw_parts_mut(ptr.cast_mut().cast(), len)
+    }
+}
+
+unsafe impl<T: Send> Send for PackedSliceBox<T> where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>
+{
+}
+unsafe impl<T: Sync> Sync for PackedSliceBox<T> where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>
+{
+}
+
+impl<T> Drop for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.as_ptr()) });
+    }
+}
+
+impl<T> Deref for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl<T> DerefMut for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { &mut *self.as_ptr() }
+    }
+}
+
+impl<T> TryFrom<Box<[T]>> for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    type Error = Box<[T]>;
+
+    fn try_from(value: Box<[T]>) -> Result<Self, Box<[T]>> {
+        Self::new(value)
+    }
+}
+
+impl<T> TryFrom<Vec<T>> for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    type Error = Box<[T]>;
+
+    fn try_from(value: Vec<T>) -> Result<Self, Box<[T]>> {
+        Self::new(value.into_boxed_slice())
+    }
+}
+
+impl<T: Clone> Clone for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    fn clone(&self) -> Self {
+        unsafe { Self::new_unchecked(Box::from(&**self)) }
+    }
+}
+
+impl<T: Debug> Debug for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq> Eq for PackedSliceBox<T> where FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true> {}
+
+impl<T: Hash> Hash for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+/// The length is stored right below the high bits still left unused by [`Box`].
+unsafe impl<T> Pointer for PackedSliceBox<T>
+where
+    FreeHighBits<Box<T>, LEN_BITS>: Eval<RESULT = true>,
+{
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = align_of::<T>();
+    const HIGH_BITS: u32 = <Box<T>>::HIGH_BITS - LEN_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
@@ -0,0 +1,154 @@
+use core::{fmt, ptr};
+
+use crate::{Bits, Eval, FitsInUsize, MaybeOwned, NotZero, Pointer};
+
+
+/// Signed integers at most `N` bits long, shifted left to leave the low bits free.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SmallInt<const N: u32>(isize);
+
+impl<const N: u32> SmallInt<N>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+{
+    const SHIFT: u32 = usize::BITS - N;
+    pub const MIN: isize = isize::MIN >> Self::SHIFT;
+    pub const MAX: isize = isize::MAX >> Self::SHIFT;
+
+    pub const fn new(value: isize) -> Option<Self> {
+        if value < Self::MIN || value > Self::MAX {
+            None
+        } else {
+            Some(Self(value))
+        }
+    }
+
+    pub const fn new_wrapping(value: isize) -> Self {
+        Self((value << Self::SHIFT) >> Self::SHIFT)
+    }
+
+    pub const fn value(self) -> isize {
+        self.0
+    }
+}
+
+unsafe impl<const N: u32> Pointer for SmallInt<N>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+{
+    const ALIGNMENT: usize = 1 << Self::SHIFT;
+    const CLONE_IN_PLACE: bool = true;
+
+    fn into_ptr(value: Self) -> *const () {
+        ptr::without_provenance((value.0 as usize) << Self::SHIFT)
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self((ptr.addr() as isize) >> Self::SHIFT))
+    }
+}
+
+
+/// Unsigned integers at most `N` bits long, stored as [`Bits<N>`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SmallUint<const N: u32>(Bits<N>);
+
+impl<const N: u32> SmallUint<N>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+{
+    pub const MIN: usize = 0;
+    pub const MAX: usize = Bits::<N>::MASK;
+
+    pub const fn new(value: usize) -> Option<Self> {
+        match Bits::new(value) {
+            Some(bits) => Some(Self(bits)),
+            None => None,
+        }
+    }
+
+    pub const fn new_wrapping(value: usize) -> Self {
+        Self(Bits::new_masked(value))
+    }
+
+    pub const fn value(self) -> usize {
+        self.0.value()
+    }
+}
+
+unsafe impl<const N: u32> Pointer for SmallUint<N>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+{
+    const ALIGNMENT: usize = Bits::<N>::ALIGNMENT;
+    const CLONE_IN_PLACE: bool = true;
+
+    fn into_ptr(value: Self) -> *const () {
+        Bits::into_ptr(value.0)
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        unsafe { Bits::from_ptr(ptr).map(Self) }
+    }
+}
+
+
+macro_rules! arithmetic {
+    ($name:ident, $int:ty) => {
+        impl<const N: u32> $name<N>
+        where
+            FitsInUsize<N>: Eval<RESULT = true>,
+            NotZero<N>: Eval<RESULT = true>,
+        {
+            pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+                match self.value().checked_add(rhs.value()) {
+                    Some(x) => Self::new(x),
+                    None => None,
+                }
+            }
+
+            pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+                match self.value().checked_sub(rhs.value()) {
+                    Some(x) => Self::new(x),
+                    None => None,
+                }
+            }
+
+            pub const fn checked_mul(self, rhs: Self) -> Option<Self> {
+                match self.value().checked_mul(rhs.value()) {
+                    Some(x) => Self::new(x),
+                    None => None,
+                }
+            }
+
+            pub const fn wrapping_add(self, rhs: Self) -> Self {
+                Self::new_wrapping(self.value().wrapping_add(rhs.value()))
+            }
+
+            pub const fn wrapping_sub(self, rhs: Self) -> Self {
+                Self::new_wrapping(self.value().wrapping_sub(rhs.value()))
+            }
+
+            pub const fn wrapping_mul(self, rhs: Self) -> Self {
+                Self::new_wrapping(self.value().wrapping_mul(rhs.value()))
+            }
+        }
+
+        impl<const N: u32> fmt::Display for $name<N>
+        where
+            FitsInUsize<N>: Eval<RESULT = true>,
+            NotZero<N>: Eval<RESULT = true>,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                <$int as fmt::Display>::fmt(&self.value(), f)
+            }
+        }
+    };
+}
+
+arithmetic!(SmallInt, isize);
+arithmetic!(SmallUint, usize);
@@ -0,0 +1,200 @@
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    mem::{align_of, size_of, ManuallyDrop},
+    ops::Deref,
+    ptr, slice, str,
+};
+
+use crate::{MaybeOwned, Pointer, INLINE, TAG_BYTE};
+
+
+/// An immutable string, stored inline if it is short enough and on the heap otherwise.
+///
+/// Up to [`SmallStr::INLINE_CAPACITY`] bytes are kept in the pointer itself, with the length in
+/// the low byte. Longer strings are stored after a length header in a single allocation.
+pub struct SmallStr(*const ());
+
+const LEN_SHIFT: u32 = 3;
+const LEN_MASK: usize = 0b111;
+
+/// Memory offset of the first inline byte.
+#[cfg(target_endian = "little")]
+const DATA_BYTE: usize = 1;
+
+#[cfg(target_endian = "big")]
+const DATA_BYTE: usize = 0;
+
+#[repr(C, align(8))]
+struct Header {
+    len: usize,
+}
+
+impl SmallStr {
+    pub const INLINE_CAPACITY: usize = size_of::<usize>() - 1;
+
+    pub fn new(s: &str) -> Self {
+        if s.len() <= Self::INLINE_CAPACITY {
+            let mut bytes = [0; size_of::<usize>()];
+            bytes[TAG_BYTE] = (INLINE | (s.len() << LEN_SHIFT)) as u8;
+            bytes[DATA_BYTE .. DATA_BYTE + s.len()].copy_from_slice(s.as_bytes());
+            return Self(ptr::without_provenance(usize::from_ne_bytes(bytes)));
+        }
+
+        let layout = Self::layout(s.len());
+
+        unsafe {
+            let header = alloc(layout);
+            if header.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            header.cast::<Header>().write(Header { len: s.len() });
+            let data = header.add(size_of::<Header>());
+            ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+            Self(header.cast_const().cast())
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.0.addr() & INLINE != 0
+    }
+
+    pub fn as_str(&self) -> &str {
+        let bytes = if self.is_inline() {
+            let len = (self.0.addr() >> LEN_SHIFT) & LEN_MASK;
+            let data = ptr::from_ref(self).cast::<u8>();
+            unsafe { slice::from_raw_parts(data.add(DATA_BYTE), len) }
+        } else {
+            unsafe {
+                let len = (*self.0.cast::<Header>()).len;
+                let data = self.0.cast::<u8>().add(size_of::<Header>());
+                slice::from_raw_parts(data, len)
+            }
+        };
+
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+
+    fn layout(len: usize) -> Layout {
+        let size = size_of::<Header>()
+            .checked_add(len)
+            .expect("string too long");
+        Layout::from_size_align(size, align_of::<Header>()).expect("string too long")
+    }
+}
+
+unsafe impl Send for SmallStr {}
+unsafe impl Sync for SmallStr {}
+
+impl Drop for SmallStr {
+    fn drop(&mut self) {
+        if !self.is_inline() {
+            let layout = Self::layout(self.len());
+            unsafe { dealloc(self.0.cast_mut().cast(), layout) };
+        }
+    }
+}
+
+impl Clone for SmallStr {
+    fn clone(&self) -> Self {
+        if self.is_inline() {
+            Self(self.0)
+        } else {
+            Self::new(self.as_str())
+        }
+    }
+}
+
+impl Default for SmallStr {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl From<&str> for SmallStr {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Deref for SmallStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for SmallStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for SmallStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SmallStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for SmallStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<str> for SmallStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl Eq for SmallStr {}
+
+impl PartialOrd for SmallStr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallStr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+/// The bits below the inline flag are free in both representations.
+unsafe impl Pointer for SmallStr {
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = INLINE;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr))
+    }
+}
@@ -0,0 +1,156 @@
+use alloc::{
+    rc::{self, Rc},
+    sync::{self, Arc},
+};
+
+use crate::{compact::Compact, shy_atomic::ShyAtomic, MaybeOwned, Pointer};
+
+
+/// Reference counted pointers with a weak counterpart.
+pub trait Shared: Pointer + Clone {
+    type Weak: Pointer + Clone + Default;
+
+    fn downgrade(this: &Self) -> Self::Weak;
+    fn upgrade(weak: &Self::Weak) -> Option<Self>;
+}
+
+impl<T> Shared for Rc<T> {
+    type Weak = rc::Weak<T>;
+
+    fn downgrade(this: &Self) -> Self::Weak {
+        Rc::downgrade(this)
+    }
+
+    fn upgrade(weak: &Self::Weak) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+impl<T> Shared for Arc<T> {
+    type Weak = sync::Weak<T>;
+
+    fn downgrade(this: &Self) -> Self::Weak {
+        Arc::downgrade(this)
+    }
+
+    fn upgrade(weak: &Self::Weak) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+
+/// A strong or weak reference to a shared value, tagged with a single bit.
+#[derive(Debug, Clone)]
+pub enum StrongOrWeak<P: Shared> {
+    Strong(P),
+    Weak(P::Weak),
+}
+
+impl<P: Shared> StrongOrWeak<P> {
+    pub fn is_strong(&self) -> bool {
+        matches!(self, Self::Strong(_))
+    }
+
+    pub fn upgrade(&self) -> Option<P> {
+        match self {
+            Self::Strong(x) => Some(x.clone()),
+            Self::Weak(x) => P::upgrade(x),
+        }
+    }
+
+    pub fn downgrade(&self) -> P::Weak {
+        match self {
+            Self::Strong(x) => P::downgrade(x),
+            Self::Weak(x) => x.clone(),
+        }
+    }
+
+    pub fn downgrade_in_place(&mut self) {
+        if let Self::Strong(x) = self {
+            *self = Self::Weak(P::downgrade(x));
+        }
+    }
+
+    /// Returns `true` if the reference is strong afterwards.
+    pub fn try_upgrade_in_place(&mut self) -> bool {
+        if let Self::Weak(x) = self {
+            match P::upgrade(x) {
+                Some(x) => *self = Self::Strong(x),
+                None => return false,
+            }
+        }
+
+        true
+    }
+
+    fn into_result(self) -> Result<P, P::Weak> {
+        match self {
+            Self::Strong(x) => Ok(x),
+            Self::Weak(x) => Err(x),
+        }
+    }
+
+    fn from_result(value: Result<P, P::Weak>) -> Self {
+        match value {
+            Ok(x) => Self::Strong(x),
+            Err(x) => Self::Weak(x),
+        }
+    }
+}
+
+impl<P: Shared> Compact<StrongOrWeak<P>>
+where
+    Result<P, P::Weak>: Pointer,
+{
+    pub fn downgrade_in_place(&mut self) {
+        self.get_mut().downgrade_in_place();
+    }
+
+    /// Returns `true` if the reference is strong afterwards.
+    pub fn try_upgrade_in_place(&mut self) -> bool {
+        self.get_mut().try_upgrade_in_place()
+    }
+}
+
+/// These are not atomic: the value is swapped out, converted and put back with a compare-exchange,
+/// while other threads see a dangling weak reference in its place. If another value was stored
+/// meanwhile, it is kept and the converted value is handed back as an error.
+impl<P: Shared> ShyAtomic<StrongOrWeak<P>>
+where
+    Result<P, P::Weak>: Pointer,
+{
+    pub fn downgrade_by_swap(&self) -> Result<(), StrongOrWeak<P>> {
+        self.update(StrongOrWeak::downgrade_in_place)
+    }
+
+    /// Returns `Ok(true)` if the stored reference is strong afterwards.
+    pub fn try_upgrade_by_swap(&self) -> Result<bool, StrongOrWeak<P>> {
+        self.update(StrongOrWeak::try_upgrade_in_place)
+    }
+
+    fn update<R>(&self, f: impl FnOnce(&mut StrongOrWeak<P>) -> R) -> Result<R, StrongOrWeak<P>> {
+        let placeholder = StrongOrWeak::Weak(P::Weak::default());
+        let mut value = self.swap(placeholder.clone());
+        let result = f(&mut value);
+        self.compare_exchange(&placeholder, value).map(|_| result)
+    }
+}
+
+/// Encoded as `Result<P, P::Weak>`.
+unsafe impl<P: Shared> Pointer for StrongOrWeak<P>
+where
+    Result<P, P::Weak>: Pointer,
+{
+    const NON_NULL: bool = <Result<P, P::Weak>>::NON_NULL;
+    const ALIGNMENT: usize = <Result<P, P::Weak>>::ALIGNMENT;
+    const CLONE_IN_PLACE: bool = <Result<P, P::Weak>>::CLONE_IN_PLACE;
+    const HIGH_BITS: u32 = <Result<P, P::Weak>>::HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        <Result<P, P::Weak>>::into_ptr(value.into_result())
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        unsafe { <Result<P, P::Weak>>::from_ptr(ptr).map(Self::from_result) }
+    }
+}
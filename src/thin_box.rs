@@ -0,0 +1,237 @@
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error, Layout},
+    boxed::Box,
+    string::String,
+    vec::Vec,
+};
+use core::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::{PhantomData, Unsize},
+    mem::{align_of, size_of, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr,
+    ptr::Pointee,
+};
+
+use crate::{MaybeOwned, Pointer, ADDRESS_HIGH_BITS};
+
+
+/// A [`Box`] for unsized values which is a single pointer wide.
+///
+/// The pointer metadata, e.g. the length of a slice, is stored on the heap right before the value.
+pub struct ThinBox<T: ?Sized>(*const (), PhantomData<T>);
+
+/// A thin owned trait object, e.g. `ThinDyn<dyn Fn()>`, with the vtable stored on the heap.
+pub type ThinDyn<T> = ThinBox<T>;
+
+impl<T> ThinBox<T> {
+    pub fn new(value: T) -> Self {
+        let value = ManuallyDrop::new(value);
+        unsafe { Self::from_raw(ptr::from_ref(&*value).cast_mut()) }
+    }
+}
+
+impl<T: ?Sized> ThinBox<T> {
+    /// Store `value` behind a pointer to the unsized type `T`, e.g. a trait object.
+    pub fn new_unsize<U: Unsize<T>>(value: U) -> Self {
+        let value = ManuallyDrop::new(value);
+        let value: *const T = ptr::from_ref(&*value);
+        unsafe { Self::from_raw(value.cast_mut()) }
+    }
+
+    pub fn into_box(self) -> Box<T> {
+        let value = ManuallyDrop::new(self);
+        let src = value.as_ptr();
+        let layout = Layout::for_value(unsafe { &*src });
+
+        let dst = if layout.size() == 0 {
+            ptr::without_provenance_mut(layout.align())
+        } else {
+            let dst = unsafe { alloc(layout) };
+            if dst.is_null() {
+                handle_alloc_error(layout);
+            }
+            dst
+        };
+
+        unsafe {
+            ptr::copy_nonoverlapping(src.cast::<u8>(), dst, layout.size());
+            dealloc_with_header::<<T as Pointee>::Metadata>(value.0, layout);
+            Box::from_raw(ptr::from_raw_parts_mut(
+                dst.cast::<()>(),
+                ptr::metadata(src),
+            ))
+        }
+    }
+
+    fn as_ptr(&self) -> *mut T {
+        let meta = unsafe { *header::<<T as Pointee>::Metadata>(self.0) };
+        ptr::from_raw_parts_mut(self.0.cast_mut(), meta)
+    }
+
+    /// Move the value out of `value` without running its destructor.
+    unsafe fn from_raw(value: *mut T) -> Self {
+        let layout = Layout::for_value(unsafe { &*value });
+        let ptr = alloc_with_header(layout, ptr::metadata(value));
+        let dst = ptr.cast_mut().cast();
+        unsafe { ptr::copy_nonoverlapping(value.cast::<u8>(), dst, layout.size()) };
+        Self(ptr, PhantomData)
+    }
+}
+
+unsafe impl<T: ?Sized + Send> Send for ThinBox<T> {}
+unsafe impl<T: ?Sized + Sync> Sync for ThinBox<T> {}
+
+impl<T: ?Sized> Drop for ThinBox<T> {
+    fn drop(&mut self) {
+        let value = self.as_ptr();
+        let layout = Layout::for_value(unsafe { &*value });
+
+        unsafe {
+            ptr::drop_in_place(value);
+            dealloc_with_header::<<T as Pointee>::Metadata>(self.0, layout);
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ThinBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ThinBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.as_ptr() }
+    }
+}
+
+impl<T: ?Sized> From<Box<T>> for ThinBox<T> {
+    fn from(value: Box<T>) -> Self {
+        let layout = Layout::for_value(&*value);
+        let value = Box::into_raw(value);
+        let thin = unsafe { Self::from_raw(value) };
+
+        if layout.size() != 0 {
+            unsafe { dealloc(value.cast(), layout) };
+        }
+
+        thin
+    }
+}
+
+impl<T: Clone> From<&[T]> for ThinBox<[T]> {
+    fn from(value: &[T]) -> Self {
+        Box::<[T]>::from(value).into()
+    }
+}
+
+impl<T> From<Vec<T>> for ThinBox<[T]> {
+    fn from(value: Vec<T>) -> Self {
+        value.into_boxed_slice().into()
+    }
+}
+
+impl From<&str> for ThinBox<str> {
+    fn from(value: &str) -> Self {
+        let bytes = ThinBox::<[u8]>::from(value.as_bytes());
+        ThinBox(ManuallyDrop::new(bytes).0, PhantomData)
+    }
+}
+
+impl From<String> for ThinBox<str> {
+    fn from(value: String) -> Self {
+        value.into_boxed_str().into()
+    }
+}
+
+impl<T: Clone> Clone for ThinBox<[T]> {
+    fn clone(&self) -> Self {
+        Self::from(&**self)
+    }
+}
+
+impl Clone for ThinBox<str> {
+    fn clone(&self) -> Self {
+        Self::from(&**self)
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for ThinBox<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for ThinBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for ThinBox<T> {}
+
+impl<T: ?Sized + Hash> Hash for ThinBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+/// Points to the value, which is aligned at least as much as the metadata before it.
+unsafe impl<T: ?Sized> Pointer for ThinBox<T> {
+    const NON_NULL: bool = true;
+    const ALIGNMENT: usize = align_of::<<T as Pointee>::Metadata>();
+    const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+    fn into_ptr(value: Self) -> *const () {
+        ManuallyDrop::new(value).0
+    }
+
+    unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+        MaybeOwned::new(Self(ptr, PhantomData))
+    }
+}
+
+
+/// Layout of a value preceded by a header `H`, and the offset of the value.
+///
+/// The allocation is never empty, even if both the header and the value are zero-sized.
+fn layout_with_header<H>(value: Layout) -> (Layout, usize) {
+    let align = value.align().max(align_of::<H>());
+    let offset = size_of::<H>().next_multiple_of(align);
+    let size = offset.checked_add(value.size()).expect("value too large");
+    let layout = Layout::from_size_align(size.max(1), align).expect("value too large");
+    (layout.pad_to_align(), offset)
+}
+
+/// Allocate space for a value with the given layout, preceded by `header`.
+///
+/// Returns a pointer to the uninitialized value.
+pub(crate) fn alloc_with_header<H>(value: Layout, header: H) -> *const () {
+    let (layout, offset) = layout_with_header::<H>(value);
+
+    unsafe {
+        let base = alloc(layout);
+        if base.is_null() {
+            handle_alloc_error(layout);
+        }
+
+        let ptr = base.add(offset);
+        ptr.cast::<H>().sub(1).write(header);
+        ptr.cast_const().cast()
+    }
+}
+
+/// The header right before a value allocated by [`alloc_with_header`].
+pub(crate) unsafe fn header<H>(ptr: *const ()) -> *const H {
+    unsafe { ptr.cast::<H>().sub(1) }
+}
+
+/// Free an allocation made by [`alloc_with_header`], without dropping its contents.
+pub(crate) unsafe fn dealloc_with_header<H>(ptr: *const (), value: Layout) {
+    let (layout, offset) = layout_with_header::<H>(value);
+    unsafe { dealloc(ptr.cast_mut().cast::<u8>().sub(offset), layout) };
+}
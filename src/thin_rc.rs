@@ -0,0 +1,253 @@
+use alloc::{
+    alloc::{dealloc, Layout},
+    boxed::Box,
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    cell::Cell,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::{PhantomData, Unsize},
+    mem::{align_of, ManuallyDrop},
+    ops::Deref,
+    ptr,
+    ptr::Pointee,
+    sync::atomic::{fence, AtomicUsize, Ordering::*},
+};
+
+use crate::{
+    thin_box::{alloc_with_header, dealloc_with_header, header},
+    MaybeOwned, Pointer, ADDRESS_HIGH_BITS,
+};
+
+
+/// Reference counters for [`ThinRc`] and [`ThinArc`].
+pub(crate) trait Count {
+    fn one() -> Self;
+    fn increment(&self);
+
+    /// Returns `true` if the last reference was released.
+    fn decrement(&self) -> bool;
+}
+
+impl Count for Cell<usize> {
+    fn one() -> Self {
+        Cell::new(1)
+    }
+
+    fn increment(&self) {
+        self.set(self.get().checked_add(1).expect("reference count overflow"));
+    }
+
+    fn decrement(&self) -> bool {
+        self.set(self.get() - 1);
+        self.get() == 0
+    }
+}
+
+impl Count for AtomicUsize {
+    fn one() -> Self {
+        AtomicUsize::new(1)
+    }
+
+    fn increment(&self) {
+        let old = self.fetch_add(1, Relaxed);
+        assert!(old < isize::MAX as usize, "reference count overflow");
+    }
+
+    fn decrement(&self) -> bool {
+        if self.fetch_sub(1, Release) != 1 {
+            return false;
+        }
+
+        fence(Acquire);
+        true
+    }
+}
+
+#[repr(C)]
+struct Header<C, M> {
+    count: C,
+    meta: M,
+}
+
+
+macro_rules! thin_rc {
+    ($(#[$attr:meta])* $name:ident, $count:ty, $std:ident) => {
+        $(#[$attr])*
+        pub struct $name<T: ?Sized>(*const (), PhantomData<T>);
+
+        impl<T> $name<T> {
+            pub fn new(value: T) -> Self {
+                let value = ManuallyDrop::new(value);
+                unsafe { Self::from_raw(ptr::from_ref(&*value)) }
+            }
+        }
+
+        impl<T: ?Sized> $name<T> {
+            /// Store `value` behind a pointer to the unsized type `T`, e.g. a trait object.
+            pub fn new_unsize<U: Unsize<T>>(value: U) -> Self {
+                let value = ManuallyDrop::new(value);
+                let value: *const T = ptr::from_ref(&*value);
+                unsafe { Self::from_raw(value) }
+            }
+
+            /// Copy the value of a standard reference counted pointer into a new allocation.
+            ///
+            /// The result has its own reference count, slices are copied by cloning each element.
+            pub fn copy_from(value: &$std<T>) -> Self
+            where
+                for<'a> Self: From<&'a T>,
+            {
+                Self::from(&**value)
+            }
+
+            pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+                this.0 == other.0
+            }
+
+            fn header(&self) -> &Header<$count, <T as Pointee>::Metadata> {
+                unsafe { &*header(self.0) }
+            }
+
+            fn as_ptr(&self) -> *const T {
+                ptr::from_raw_parts(self.0, self.header().meta)
+            }
+
+            /// Move the value out of `value` without running its destructor.
+            unsafe fn from_raw(value: *const T) -> Self {
+                let layout = Layout::for_value(unsafe { &*value });
+                let header = Header { count: <$count>::one(), meta: ptr::metadata(value) };
+                let ptr = alloc_with_header(layout, header);
+                let dst = ptr.cast_mut().cast();
+                unsafe { ptr::copy_nonoverlapping(value.cast::<u8>(), dst, layout.size()) };
+                Self(ptr, PhantomData)
+            }
+        }
+
+        impl<T: ?Sized> Drop for $name<T> {
+            fn drop(&mut self) {
+                if !self.header().count.decrement() {
+                    return;
+                }
+
+                let value = self.as_ptr();
+                let layout = Layout::for_value(unsafe { &*value });
+
+                unsafe {
+                    ptr::drop_in_place(value.cast_mut());
+                    dealloc_with_header::<Header<$count, <T as Pointee>::Metadata>>(self.0, layout);
+                }
+            }
+        }
+
+        impl<T: ?Sized> Clone for $name<T> {
+            fn clone(&self) -> Self {
+                self.header().count.increment();
+                Self(self.0, PhantomData)
+            }
+        }
+
+        impl<T: ?Sized> Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                unsafe { &*self.as_ptr() }
+            }
+        }
+
+        impl<T: ?Sized> From<Box<T>> for $name<T> {
+            fn from(value: Box<T>) -> Self {
+                let layout = Layout::for_value(&*value);
+                let value = Box::into_raw(value);
+                let thin = unsafe { Self::from_raw(value) };
+
+                if layout.size() != 0 {
+                    unsafe { dealloc(value.cast(), layout) };
+                }
+
+                thin
+            }
+        }
+
+        impl<T: Clone> From<&[T]> for $name<[T]> {
+            fn from(value: &[T]) -> Self {
+                Box::<[T]>::from(value).into()
+            }
+        }
+
+        impl<T> From<Vec<T>> for $name<[T]> {
+            fn from(value: Vec<T>) -> Self {
+                value.into_boxed_slice().into()
+            }
+        }
+
+        impl From<&str> for $name<str> {
+            fn from(value: &str) -> Self {
+                Box::<str>::from(value).into()
+            }
+        }
+
+        impl From<String> for $name<str> {
+            fn from(value: String) -> Self {
+                value.into_boxed_str().into()
+            }
+        }
+
+        impl<T: ?Sized + Debug> Debug for $name<T> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                (**self).fmt(f)
+            }
+        }
+
+        impl<T: ?Sized + PartialEq> PartialEq for $name<T> {
+            fn eq(&self, other: &Self) -> bool {
+                **self == **other
+            }
+        }
+
+        impl<T: ?Sized + Eq> Eq for $name<T> {}
+
+        impl<T: ?Sized + Hash> Hash for $name<T> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                (**self).hash(state);
+            }
+        }
+
+        /// Points to the value, which is aligned at least as much as the header before it.
+        unsafe impl<T: ?Sized> Pointer for $name<T> {
+            const NON_NULL: bool = true;
+            const ALIGNMENT: usize = align_of::<Header<$count, <T as Pointee>::Metadata>>();
+            const CLONE_IN_PLACE: bool = true;
+            const HIGH_BITS: u32 = ADDRESS_HIGH_BITS;
+
+            fn into_ptr(value: Self) -> *const () {
+                ManuallyDrop::new(value).0
+            }
+
+            unsafe fn from_ptr(ptr: *const ()) -> MaybeOwned<Self> {
+                MaybeOwned::new(Self(ptr, PhantomData))
+            }
+        }
+    };
+}
+
+thin_rc! {
+    /// An [`Rc`] for unsized values which is a single pointer wide.
+    ///
+    /// The reference count and pointer metadata are stored on the heap right before the value.
+    ThinRc, Cell<usize>, Rc
+}
+
+thin_rc! {
+    /// An [`Arc`] for unsized values which is a single pointer wide.
+    ///
+    /// The reference count and pointer metadata are stored on the heap right before the value.
+    ThinArc, AtomicUsize, Arc
+}
+
+unsafe impl<T: ?Sized + Send + Sync> Send for ThinArc<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for ThinArc<T> {}
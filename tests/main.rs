@@ -1,21 +1,50 @@
 #![feature(associated_const_equality)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![feature(pointer_is_aligned_to)]
 
-use std::{fmt::Debug, num::NonZeroUsize, ops::Deref, ptr, rc, rc::Rc, sync, sync::Arc};
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    marker::PhantomPinned,
+    mem,
+    num::{NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize},
+    ops::Deref,
+    pin::Pin,
+    ptr, rc,
+    rc::Rc,
+    sync,
+    sync::Arc,
+};
 
 use proptest::{
     array::{uniform, UniformArrayStrategy},
-    bits, num,
+    bits, bool,
+    collection::vec,
+    num,
     num::usize,
     option::OptionStrategy,
-    proptest,
+    prelude::any,
+    prop_oneof, proptest,
     result::MaybeOk,
     sample::{select, Select},
     strategy::{Just, Strategy},
 };
 use viaptr::{
-    compact::Compact, shy_atomic::ShyAtomic, AlignedTo, Bits, CloneInPlace, Eval, FitsInUsize,
-    NestOption, NonNull, Null, Num, Pointer,
+    aligned_box::{AlignedArc, AlignedBox, AlignedRc},
+    compact::Compact,
+    compact_value::{CompactValue, Int, ValueRef},
+    inline_or_box::{Inline, InlineOrBox},
+    one_of::OneOf,
+    pointer_dispatch, pointer_enum, pointer_struct,
+    shy_atomic::ShyAtomic,
+    small_int::{SmallInt, SmallUint},
+    small_str::SmallStr,
+    strong_or_weak::StrongOrWeak,
+    tag_enum,
+    thin_box::{ThinBox, ThinDyn},
+    thin_rc::{ThinArc, ThinRc},
+    AlignedTo, Bits, CloneInPlace, Eval, FitsInUsize, Flags, MutRef, NestOption, NonNull, NotZero,
+    Null, Num, Pointer,
 };
 
 
@@ -156,6 +185,363 @@ fn compound_cip() -> impl Strategy<Value: CloneInPlace + Debug + PartialEq> {
     )
 }
 
+pointer_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Quad {
+        A(Rc<usize>),
+        B(Arc<Aligned>),
+        C(&'static usize),
+        D(Bits<20>),
+    }
+}
+
+pointer_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Triple {
+        Boxed(Option<Box<usize>>),
+        Tagged((Arc<Aligned>, Num<5>)),
+        Empty(Null),
+    }
+}
+
+pointer_struct! {
+    #[derive(Debug, Clone, PartialEq)]
+    struct Node {
+        ptr: Arc<Aligned>,
+        red: bool,
+        state: Bits<2>,
+        kind: Num<3>,
+    }
+}
+
+fn quad() -> impl Strategy<Value = Quad> {
+    prop_oneof![
+        rc(usize()).prop_map(Quad::A),
+        arc(aligned()).prop_map(Quad::B),
+        some_ref().prop_map(Quad::C),
+        bits::<20>().prop_map(Quad::D),
+    ]
+}
+
+fn triple() -> impl Strategy<Value = Triple> {
+    prop_oneof![
+        option(boxed(usize())).prop_map(Triple::Boxed),
+        (arc(aligned()), num::<5>()).prop_map(Triple::Tagged),
+        null().prop_map(Triple::Empty),
+    ]
+}
+
+fn node() -> impl Strategy<Value = Node> {
+    (arc(aligned()), bool::ANY, bits::<2>(), num::<3>()).prop_map(|(ptr, red, state, kind)| Node {
+        ptr,
+        red,
+        state,
+        kind,
+    })
+}
+
+type Five = OneOf<(
+    Box<usize>,
+    Rc<usize>,
+    Arc<usize>,
+    &'static usize,
+    Arc<Aligned>,
+)>;
+
+fn five() -> impl Strategy<Value = Five> {
+    prop_oneof![
+        boxed(usize()).prop_map(OneOf::new),
+        rc(usize()).prop_map(OneOf::new),
+        arc(usize()).prop_map(OneOf::new),
+        some_ref().prop_map(OneOf::new),
+        arc(aligned()).prop_map(OneOf::new),
+    ]
+}
+
+type Duo = OneOf<(Arc<usize>, Option<Rc<usize>>)>;
+
+fn duo() -> impl Strategy<Value = Duo> {
+    prop_oneof![
+        arc(usize()).prop_map(OneOf::new),
+        option(rc(usize())).prop_map(OneOf::new),
+    ]
+}
+
+type Mixed = OneOf<(Box<u16>, Box<usize>, Rc<usize>, Arc<usize>, &'static usize)>;
+
+fn mixed() -> impl Strategy<Value = Mixed> {
+    prop_oneof![
+        any::<u16>().prop_map(|x| OneOf::new(Box::new(x))),
+        boxed(usize()).prop_map(OneOf::new),
+        rc(usize()).prop_map(OneOf::new),
+        arc(usize()).prop_map(OneOf::new),
+        some_ref().prop_map(OneOf::new),
+    ]
+}
+
+type Wide = OneOf<(Arc<Aligned>, Null, Rc<Aligned>, Option<Box<usize>>)>;
+
+fn wide() -> impl Strategy<Value = Wide> {
+    prop_oneof![
+        arc(aligned()).prop_map(OneOf::new),
+        null().prop_map(OneOf::new),
+        rc(aligned()).prop_map(OneOf::new),
+        option(boxed(usize())).prop_map(OneOf::new),
+    ]
+}
+
+pointer_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Uneven {
+        Short(Rc<Aligned>),
+        Narrow(Box<u16>),
+        Long(Bits<4>),
+    }
+}
+
+fn uneven() -> impl Strategy<Value = Uneven> {
+    prop_oneof![
+        rc(aligned()).prop_map(Uneven::Short),
+        any::<u16>().prop_map(|x| Uneven::Narrow(Box::new(x))),
+        bits::<4>().prop_map(Uneven::Long),
+    ]
+}
+
+pointer_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Many {
+        V0(Bits<8>), V1(Bits<8>), V2(Bits<8>), V3(Bits<8>), V4(Bits<8>), V5(Bits<8>),
+        V6(Bits<8>), V7(Bits<8>), V8(Bits<8>), V9(Bits<8>), V10(Bits<8>), V11(Bits<8>),
+        V12(Bits<8>), V13(Bits<8>), V14(Bits<8>), V15(Bits<8>), V16(Bits<8>), V17(Bits<8>),
+    }
+}
+
+fn many() -> impl Strategy<Value = Many> {
+    use Many::*;
+    const VARIANTS: &[fn(Bits<8>) -> Many] = &[
+        V0, V1, V2, V3, V4, V5, V6, V7, V8, V9, V10, V11, V12, V13, V14, V15, V16, V17,
+    ];
+    (select(VARIANTS), bits::<8>()).prop_map(|(f, x)| f(x))
+}
+
+fn inline_or_box<T: Strategy<Value: Inline>>(x: T) -> impl Strategy<Value = InlineOrBox<T::Value>> {
+    x.prop_map(InlineOrBox::new)
+}
+
+fn thin_slice<T: Strategy<Value: Clone>>(x: T) -> impl Strategy<Value = ThinBox<[T::Value]>> {
+    vec(x, 0 .. 5).prop_map(ThinBox::from)
+}
+
+fn thin_str() -> impl Strategy<Value = ThinBox<str>> {
+    "\\PC{0,12}".prop_map(ThinBox::from)
+}
+
+fn thin_rc_str() -> impl Strategy<Value = ThinRc<str>> {
+    "\\PC{0,12}".prop_map(|s| ThinRc::copy_from(&Rc::<str>::from(s)))
+}
+
+fn thin_arc_slice<T: Strategy<Value: Clone>>(x: T) -> impl Strategy<Value = ThinArc<[T::Value]>> {
+    vec(x, 0 .. 5).prop_map(ThinArc::from)
+}
+
+fn pinned<T: Strategy<Value: Deref<Target: Unpin>>>(x: T) -> impl Strategy<Value = Pin<T::Value>> {
+    x.prop_map(Pin::new)
+}
+
+fn aligned_box<T: Strategy, const N: usize>(
+    x: T,
+) -> impl Strategy<Value = AlignedBox<T::Value, N>> {
+    x.prop_map(AlignedBox::new)
+}
+
+fn aligned_rc<T: Strategy, const N: usize>(x: T) -> impl Strategy<Value = AlignedRc<T::Value, N>> {
+    x.prop_map(AlignedRc::new)
+}
+
+fn aligned_arc<T: Strategy, const N: usize>(
+    x: T,
+) -> impl Strategy<Value = AlignedArc<T::Value, N>> {
+    x.prop_map(AlignedArc::new)
+}
+
+fn small_str() -> impl Strategy<Value = SmallStr> {
+    "\\PC{0,12}".prop_map(|s| SmallStr::new(&s))
+}
+
+fn small_int<const N: u32>() -> impl Strategy<Value = SmallInt<N>>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+{
+    (SmallInt::<N>::MIN ..= SmallInt::<N>::MAX).prop_map(|x| SmallInt::new(x).unwrap())
+}
+
+fn small_uint<const N: u32>() -> impl Strategy<Value = SmallUint<N>>
+where
+    FitsInUsize<N>: Eval<RESULT = true>,
+    NotZero<N>: Eval<RESULT = true>,
+{
+    (0 ..= SmallUint::<N>::MAX).prop_map(|x| SmallUint::new(x).unwrap())
+}
+
+fn compact_value() -> impl Strategy<Value = CompactValue> {
+    let leaf = prop_oneof![
+        Just(CompactValue::null()),
+        bool::ANY.prop_map(CompactValue::from),
+        (Int::MIN ..= Int::MAX).prop_map(|x| CompactValue::int(x).unwrap()),
+        "\\PC{0,12}".prop_map(CompactValue::from),
+    ];
+
+    leaf.prop_recursive(3, 16, 4, |x| {
+        prop_oneof![
+            vec(x.clone(), 0 .. 4).prop_map(CompactValue::from),
+            vec(("[a-z]{0,4}", x), 0 .. 4).prop_map(|v| v.into_iter().collect()),
+        ]
+    })
+}
+
+tag_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Color {
+        Red,
+        Black,
+    }
+}
+
+tag_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Shape {
+        Circle,
+        Square,
+        Triangle,
+        Hexagon,
+        Star,
+    }
+}
+
+fn color() -> Select<Color> {
+    select(&[Color::Red, Color::Black])
+}
+
+fn shape() -> Select<Shape> {
+    select(&[
+        Shape::Circle,
+        Shape::Square,
+        Shape::Triangle,
+        Shape::Hexagon,
+        Shape::Star,
+    ])
+}
+
+fn ordering() -> Select<Ordering> {
+    select(&[Ordering::Less, Ordering::Equal, Ordering::Greater])
+}
+
+tag_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Mark {
+        Dirty,
+        Pinned,
+        Visited,
+    }
+}
+
+fn flags() -> impl Strategy<Value = Flags<Mark>> {
+    bits::usize::masked(Flags::<Mark>::MASK).prop_map(Flags::from_bits_truncate)
+}
+
+fn mark() -> Select<Mark> {
+    select(&[Mark::Dirty, Mark::Pinned, Mark::Visited])
+}
+
+#[derive(Debug, PartialEq)]
+struct Unmovable(usize, PhantomPinned);
+
+impl Unmovable {
+    fn bump(self: Pin<&mut Self>, by: usize) {
+        let this = unsafe { self.get_unchecked_mut() };
+        this.0 = this.0.wrapping_add(by);
+    }
+}
+
+trait Expr {
+    fn eval(&self, env: usize) -> usize;
+    fn name(&self) -> &str;
+    fn bump(&mut self, by: usize);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Lit(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Var(String);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Add(Vec<usize>);
+
+impl Expr for Lit {
+    fn eval(&self, _: usize) -> usize {
+        self.0
+    }
+
+    fn name(&self) -> &str {
+        "lit"
+    }
+
+    fn bump(&mut self, by: usize) {
+        self.0 = self.0.wrapping_add(by);
+    }
+}
+
+impl Expr for Var {
+    fn eval(&self, env: usize) -> usize {
+        env
+    }
+
+    fn name(&self) -> &str {
+        &self.0
+    }
+
+    fn bump(&mut self, by: usize) {
+        self.0.push_str(&by.to_string());
+    }
+}
+
+impl Expr for Add {
+    fn eval(&self, env: usize) -> usize {
+        self.0.iter().fold(env, |x, y| x.wrapping_add(*y))
+    }
+
+    fn name(&self) -> &str {
+        "add"
+    }
+
+    fn bump(&mut self, by: usize) {
+        self.0.push(by);
+    }
+}
+
+pointer_dispatch! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum AnyExpr: Expr {
+        Lit(Lit),
+        Var(Var),
+        Add(Add),
+    }
+
+    fn eval(&self, env: usize) -> usize;
+    fn name(&self) -> &str;
+    fn bump(&mut self, by: usize);
+}
+
+fn any_expr() -> impl Strategy<Value = AnyExpr> {
+    prop_oneof![
+        usize().prop_map(|x| AnyExpr::Lit(Box::new(Lit(x)))),
+        "[a-z]{1,8}".prop_map(|x| AnyExpr::Var(Box::new(Var(x)))),
+        vec(usize(), 0 .. 4).prop_map(|x| AnyExpr::Add(Box::new(Add(x)))),
+    ]
+}
+
 
 macro_rules! gen {
     ($($name:ident ($($test:ident),+) $strategy:expr;)+) => {
@@ -205,6 +591,95 @@ gen! {
     c1 (P) compound();
     c2 (P, C) compound_cip();
     c3 (P, N, C) result((arc(aligned()), num::<30>()), (rc(aligned()), bits::<5>()));
+
+    e1 (P, N, A, C) quad();
+    e2 (P, A) triple();
+    e3 (P, N) result(quad(), triple());
+
+    s1 (P, N, A, C) node();
+    s2 (P, N, C) result(node(), (arc(aligned()), bits::<5>()));
+    s3 (P, N, C) (node(), bits::<1>());
+
+    o1 (P, N) five();
+    o2 (P, N, A, C) duo();
+    o3 (P, C) result(duo(), duo());
+    o4 (P, N) mixed();
+    o5 (P, A) wide();
+    o6 (P, A) (wide(), bits::<1>());
+    o7 (P) uneven();
+    o8 (P, C) many();
+
+    t1 (P, N, A, C) (some_ref(), bool::ANY);
+    t2 (P, N, A, C) (arc(aligned()), bits::<2>(), num::<3>());
+    t3 (P, N, C) (arc(aligned()), bool::ANY, num::<5>(), bits::<2>());
+    t4 (P, N, A) ((boxed(aligned()), bool::ANY, num::<3>()), bits::<1>(), bits::<1>());
+
+    g1 (P, A, C) color();
+    g2 (P, A, C) shape();
+    g3 (P, A, C) ordering();
+    g4 (P, N, C) (some_ref(), color(), ordering());
+    g5 (P, N, C) (arc(aligned()), shape(), bool::ANY);
+    g6 (P, N, A) result(boxed(usize()), shape());
+
+    f1 (P, N, C) (some_ref(), flags());
+    f2 (P, N, C) (arc(aligned()), flags(), color());
+    f3 (P, N, A) result((boxed(aligned()), flags()), rc(usize()));
+
+    i1 (P, A, C) any::<u8>();
+    i2 (P, A, C) any::<i16>();
+    i3 (P, A, C) any::<u32>();
+    i4 (P, C) any::<i64>();
+    i5 (P, A, C) any::<char>();
+    i6 (P, A, C) any::<bool>();
+    i7 (P, N, A, C) any::<NonZeroU8>();
+    i8 (P, N, C) any::<NonZeroU64>();
+    i9 (P, A, C) option(any::<NonZeroU32>());
+    i10 (P, N, A) result(boxed(any::<u64>()), any::<u32>());
+    i11 (P, A, C) result(any::<i8>(), any::<char>());
+
+    ss1 (P, N, A) small_str();
+    ss2 (P, N) result(small_str(), boxed(usize()));
+    ss3 (P) option(small_str());
+    ss4 (P, N) (small_str(), bits::<2>());
+
+    ib1 (P, N, A) inline_or_box(any::<(u16, u16)>());
+    ib2 (P, N, A) inline_or_box(any::<[u8; 7]>());
+    ib3 (P, N, A) inline_or_box(any::<(u64, u64)>());
+    ib4 (P, N) result(inline_or_box(any::<u32>()), inline_or_box(any::<[u32; 3]>()));
+    ib5 (P) option(inline_or_box(any::<char>()));
+    ib6 (P, N, A) inline_or_box((0 .. 0x200u64).prop_map(|x| x << 55));
+
+    tb1 (P, N, A) thin_slice(usize());
+    tb2 (P, N, A) thin_str();
+    tb3 (P, N) (thin_slice(any::<u8>()), bits::<3>());
+    tb4 (P, N, A) result(thin_str(), thin_slice(aligned()));
+    tb5 (P, A) option(thin_slice(unit()));
+
+    tr1 (P, N, A, C) thin_rc_str();
+    tr2 (P, N, A, C) thin_arc_slice(usize());
+    tr3 (P, N, C) (thin_arc_slice(aligned()), bits::<3>());
+    tr4 (P, N, A, C) result(thin_rc_str(), thin_arc_slice(any::<u8>()));
+
+    pn1 (P, N, A) pinned(boxed(usize()));
+    pn2 (P, N, A, C) pinned(arc(aligned()));
+    pn3 (P, N, A, C) (pinned(some_ref()), bool::ANY);
+    pn4 (P, N) result(pinned(boxed(usize())), pinned(rc(usize())));
+
+    ab1 (P, N, A) aligned_box::<_, 16>(any::<u8>());
+    ab2 (P, N) (aligned_box::<_, 16>(any::<u8>()), bits::<4>());
+    ab3 (P, N, A) aligned_box::<_, 2>(aligned());
+    ab4 (P, N, C) (aligned_rc::<_, 32>(unit()), bits::<5>());
+    ab5 (P, N, A, C) result(aligned_arc::<_, 4>(any::<u16>()), arc(usize()));
+    ab6 (P, N) (aligned_box::<_, 8>(unit()), bits::<3>());
+
+    pd1 (P, N, A) any_expr();
+    pd2 (P, N) result(any_expr(), rc(usize()));
+
+    si1 (P, A, C) small_int::<62>();
+    si2 (P, A, C) small_uint::<8>();
+    si3 (P, N, A, C) result(rc(usize()), small_int::<48>());
+    si4 (P, C) (small_int::<60>(), bits::<2>(), bool::ANY);
+    si5 (P, A, C) nest_option(small_uint::<62>());
 }
 
 proptest! {
@@ -214,7 +689,14 @@ proptest! {
         let x = Rc::downgrade(&src);
         let ptr = T::into_ptr(x);
         let y = unsafe { T::from_ptr(ptr).assume_owned() };
-        assert_eq!(y.upgrade(), Some(src))
+        assert_eq!(y.upgrade(), Some(src.clone()));
+
+        for (x, tag) in [(Rc::downgrade(&src), true), (rc::Weak::new(), false)] {
+            let expected = x.upgrade();
+            let c = Compact::new((x, Bits::<1>::new_masked(tag.into())));
+            assert_eq!(expected, c.get_ref().0.upgrade());
+            assert_eq!(tag, c.get_ref().1.value() == 1);
+        }
     }
 
     #[test]
@@ -223,7 +705,214 @@ proptest! {
         let x = Arc::downgrade(&src);
         let ptr = T::into_ptr(x);
         let y = unsafe { T::from_ptr(ptr).assume_owned() };
-        assert_eq!(y.upgrade(), Some(src))
+        assert_eq!(y.upgrade(), Some(src.clone()));
+
+        let s = ShyAtomic::new(Err::<Arc<usize>, _>(sync::Weak::new()));
+        let old = s.swap(Err(Arc::downgrade(&src))).unwrap_err();
+        assert!(old.upgrade().is_none());
+        let weak = s.swap(Ok(src.clone())).unwrap_err();
+        assert_eq!(Some(src), weak.upgrade());
+    }
+}
+
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+mod high_bits {
+    use proptest::{prelude::any, prop_oneof, proptest, strategy::Strategy};
+    use viaptr::{nan_box::NanBox, packed_slice::PackedSliceBox, FreeHighBits, HighBits, Pointer};
+
+    use super::{
+        aligned, arc, bits, boxed, clone_in_place, option, rc, result, select, some_ref,
+        test_aligned, test_non_null, test_pointer, usize, vec, Aligned, Arc, Compact, Eval,
+        FitsInUsize,
+    };
+
+
+    fn high<const N: u32>() -> impl Strategy<Value = HighBits<N>>
+    where
+        FitsInUsize<N>: Eval<RESULT = true>,
+    {
+        bits::usize::masked(HighBits::<N>::MASK).prop_map(HighBits::<N>::new_masked)
+    }
+
+    const SPECIAL_F64: [f64; 6] = [
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        -0.0,
+        f64::from_bits(0x7ff0_0000_0000_0001),
+        f64::from_bits(0x7ff8_dead_beef_0001),
+        // Same high bits as a stored pointer.
+        f64::from_bits(0xfff8_0000_0000_1234),
+    ];
+
+    fn nan_box<T: Strategy<Value: Pointer>>(x: T) -> impl Strategy<Value = NanBox<T::Value>>
+    where
+        FreeHighBits<T::Value, 16>: Eval<RESULT = true>,
+    {
+        prop_oneof![
+            any::<f64>().prop_map(NanBox::from_f64),
+            select(&SPECIAL_F64[.. 3]).prop_map(NanBox::from_f64),
+            x.prop_map(NanBox::from_pointer)
+        ]
+    }
+
+    fn packed<T: Strategy>(x: T) -> impl Strategy<Value = PackedSliceBox<T::Value>> {
+        vec(x, 0 .. 5).prop_map(|x| PackedSliceBox::try_from(x).unwrap())
+    }
+
+
+    gen! {
+        h1 (P, N) (boxed(any::<u8>()), high::<16>());
+        h2 (P, N, A, C) ((arc(aligned()), high::<8>()), bits::<2>());
+        h3 (P, N, A, C) ((some_ref(), bits::<2>()), high::<4>());
+        h4 (P, N, A) result((boxed(usize()), high::<8>()), (rc(usize()), high::<16>()));
+        h5 (P) option((boxed(any::<u8>()), high::<16>()));
+        h6 (P, N) ((boxed(any::<u8>()), high::<6>()), high::<10>());
+
+        ps1 (P, N, A) packed(usize());
+        ps2 (P, N) (packed(aligned()), bits::<6>());
+        ps3 (P, N, A) result(packed(usize()), boxed(usize()));
+        ps4 (P) option(packed(any::<u8>()));
+
+        nb1 (P) nan_box(boxed(usize()));
+        nb2 (P, C) nan_box(option(rc(usize())));
+        nb3 (P, C) nan_box((arc(aligned()), bits::<3>()));
+    }
+
+
+    proptest! {
+        #[test]
+        fn high_tags(x in boxed(any::<u8>()), y in high::<6>(), z in high::<10>()) {
+            assert_eq!(16, Box::<u8>::HIGH_BITS);
+            assert_eq!(10, <(Box<u8>, HighBits<6>)>::HIGH_BITS);
+            assert_eq!(0, <((Box<u8>, HighBits<6>), HighBits<10>)>::HIGH_BITS);
+            assert_eq!(8, <Result<(Box<usize>, HighBits<8>), Option<Box<Aligned>>>>::HIGH_BITS);
+
+            let ptr = Pointer::as_ptr(&x);
+            let tagged = Pointer::into_ptr(((x, y), z));
+            assert_eq!(ptr.addr(), tagged.addr() & ((1 << 48) - 1));
+            assert_eq!(y.value(), (tagged.addr() >> 48) & 0x3f);
+            assert_eq!(z.value(), tagged.addr() >> 54);
+
+            unsafe { <((Box<u8>, HighBits<6>), HighBits<10>)>::from_ptr(tagged).assume_owned() };
+        }
+
+        #[test]
+        fn nan_boxes(x in any::<f64>(), y in arc(usize()), z in select(&SPECIAL_F64)) {
+            let a = NanBox::<Arc<usize>>::from_f64(x);
+            assert_eq!(Some(x), a.as_f64());
+            assert!(a.as_pointer().is_none());
+
+            let special = NanBox::<Arc<usize>>::from_f64(z).as_f64().unwrap();
+            if z.is_nan() {
+                assert!(special.is_nan());
+            } else {
+                assert_eq!(z.to_bits(), special.to_bits());
+            }
+
+            let nan = NanBox::<Arc<usize>>::from_f64(-f64::NAN);
+            assert!(nan.as_f64().unwrap().is_nan());
+
+            let b = NanBox::from_pointer(y.clone());
+            assert_eq!(None, b.as_f64());
+            assert_eq!(Some(&y), b.as_pointer().as_deref());
+
+            let mut c = Compact::new(b.clone());
+            assert_eq!(3, Arc::strong_count(&y));
+            assert_eq!(Err(y.clone()), c.swap(a).into_inner());
+            assert_eq!(Ok(x), c.swap(nan).into_inner());
+            drop(b);
+            assert_eq!(1, Arc::strong_count(&y));
+        }
+
+        #[test]
+        fn packed_slices(x in vec(usize(), 0 .. 300)) {
+            let a = PackedSliceBox::new(x.clone().into_boxed_slice()).unwrap();
+            assert_eq!(x.len(), Pointer::as_ptr(&a).addr() >> 48);
+
+            let mut c = Compact::new(Some(a));
+            c.get_mut().as_mut().unwrap().reverse();
+            let b = c.swap(None).unwrap().into_box();
+            assert_eq!(x.iter().rev().collect::<Vec<_>>(), b.iter().collect::<Vec<_>>());
+
+            let long = vec![0_u8; PackedSliceBox::<u8>::MAX_LEN + 1];
+            assert!(PackedSliceBox::try_from(long).is_err());
+        }
+    }
+}
+
+
+#[cfg(feature = "allocator_api")]
+mod allocator_api {
+    use std::{
+        alloc::{AllocError, Allocator, Global, Layout},
+        ptr::NonNull,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering::Relaxed},
+            Arc,
+        },
+    };
+
+    use proptest::{proptest, strategy::Strategy};
+    use viaptr::{compact::Compact, shy_atomic::ShyAtomic};
+
+    use super::{
+        aligned, clone_in_place, result, test_aligned, test_non_null, test_pointer, usize, Aligned,
+    };
+
+    /// Live allocations per counter, so that tests running in parallel don't interfere.
+    static LIVE: [AtomicUsize; 2] = [const { AtomicUsize::new(0) }; 2];
+
+    #[derive(Debug, Clone, Copy, Default)]
+    struct Counting<const ID: usize>;
+
+    unsafe impl<const ID: usize> Allocator for Counting<ID> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            LIVE[ID].fetch_add(1, Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            LIVE[ID].fetch_sub(1, Relaxed);
+            unsafe { Global.deallocate(ptr, layout) };
+        }
+    }
+
+    fn boxed<T: Strategy>(x: T) -> impl Strategy<Value = Box<T::Value, Counting<0>>> {
+        x.prop_map(|x| Box::new_in(x, Counting))
+    }
+
+    fn rc<T: Strategy>(x: T) -> impl Strategy<Value = Rc<T::Value, Counting<0>>> {
+        x.prop_map(|x| Rc::new_in(x, Counting))
+    }
+
+    fn arc<T: Strategy>(x: T) -> impl Strategy<Value = Arc<T::Value, Counting<0>>> {
+        x.prop_map(|x| Arc::new_in(x, Counting))
+    }
+
+    gen! {
+        al1 (P, N, A) boxed(usize());
+        al2 (P, N, A, C) rc(usize());
+        al3 (P, N, A, C) arc(aligned());
+        al4 (P, N, A) result(boxed(usize()), arc(usize()));
+    }
+
+    proptest! {
+        #[test]
+        fn custom_alloc(x in usize(), y in aligned()) {
+            let live = || LIVE[1].load(Relaxed);
+            let mut c = Compact::new((Box::new_in(y, Counting::<1>), true));
+            c.get_mut().0 .0 = x;
+            assert_eq!((Aligned(x), true), (*c.get_ref().0, c.get_ref().1));
+
+            let s = ShyAtomic::new(Some(Arc::new_in(x, Counting::<1>)));
+            assert_eq!(2, live());
+            assert_eq!(Some(x), s.swap(None).map(|a| *a));
+            assert_eq!(1, live());
+            drop(c);
+            assert_eq!(0, live());
+        }
     }
 }
 
@@ -275,6 +964,306 @@ proptest! {
         assert_eq!(z, c.get_clone());
     }
 
+    #[test]
+    fn compact_tags(x in node(), red in bool::ANY, kind in num::<3>()) {
+        let mut c = Compact::new(x.clone());
+        assert_eq!(x.red, c.get_tag(Node::red));
+        assert_eq!(x.state, c.get_tag(Node::state));
+        assert_eq!(x.kind, c.get_tag(Node::kind));
+
+        c.set_tag(Node::red, red);
+        c.set_tag(Node::kind, kind);
+        assert_eq!(Node { red, kind, ..x }, c.get_clone());
+    }
+
+    #[test]
+    fn flag_ops(x in arc(aligned()), f in flags(), m in mark()) {
+        let mut c = Compact::new((x.clone(), f));
+        c.get_mut().1.toggle(m);
+        assert_eq!(!f.contains(m), c.get_ref().1.contains(m));
+
+        c.get_mut().1.set(m);
+        assert_eq!(f.with(m), c.get_ref().1);
+
+        let a = ShyAtomic::new(c.swap((x.clone(), Flags::empty())));
+        let (y, g) = a.swap((x.clone(), Flags::empty()));
+        assert_eq!((&x, f | m), (&y, g));
+        assert!(g.contains(m));
+        assert!(!g.without(m).contains(m));
+        assert_eq!(g, g.iter().collect());
+
+        let (_, g) = a.swap((y, Flags::all()));
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn small_strings(x in "\\PC{0,12}", y in "\\PC{0,12}") {
+        let a = SmallStr::new(&x);
+        assert_eq!(x.len() <= SmallStr::INLINE_CAPACITY, a.is_inline());
+        assert_eq!(x.as_str(), a.as_str());
+        assert_eq!(x.cmp(&y), a.cmp(&SmallStr::from(y.as_str())));
+
+        let mut c = Compact::new(Ok::<_, Box<usize>>(a.clone()));
+        assert_eq!(Ok(&a), c.get_ref().as_ref());
+        c.set(Ok(SmallStr::new(&y)));
+        assert_eq!(y.as_str(), c.get_ref().as_ref().unwrap().as_str());
+
+        let s = ShyAtomic::new(Some(a));
+        assert_eq!(Some(x.as_str()), s.swap(None).as_deref());
+    }
+
+    #[test]
+    fn inline_values(x in any::<(u16, u16)>(), y in any::<[u16; 4]>(), z in any::<u8>()) {
+        let inline = [
+            InlineOrBox::new(x).is_inline(),
+            InlineOrBox::new(y).is_inline(),
+            InlineOrBox::new([z; 7]).is_inline(),
+            InlineOrBox::new((x, x, x)).is_inline(),
+        ];
+        let word = u64::from_ne_bytes(unsafe { mem::transmute::<[u16; 4], [u8; 8]>(y) });
+        assert_eq!([true, word >> 56 == 0, true, false], inline);
+
+        let a = Compact::new((InlineOrBox::new(x), Bits::<2>::new_masked(z.into())));
+        assert_eq!(x, a.get_ref().0.get());
+
+        let b = ShyAtomic::new(InlineOrBox::new(y));
+        assert_eq!(y, b.swap(InlineOrBox::new([0; 4])).into_inner());
+    }
+
+    #[test]
+    fn thin_boxes(x in vec(usize(), 0 .. 5), y in "\\PC{0,12}") {
+        let a = ThinBox::from(x.clone().into_boxed_slice());
+        assert_eq!(x.as_slice(), &*a);
+
+        let mut c = Compact::new(a);
+        c.get_mut().reverse();
+        let mut b = c.swap(ThinBox::from(Vec::new())).into_box();
+        b.reverse();
+        assert_eq!(x.into_boxed_slice(), b);
+        assert_eq!(y.len(), *ThinBox::new(y.len()));
+
+        let s = ShyAtomic::new(Some(ThinBox::<str>::from(y.clone())));
+        assert_eq!(Some(y.into_boxed_str()), s.swap(None).map(ThinBox::into_box));
+    }
+
+    #[test]
+    fn thin_dyn(x in usize(), y in aligned()) {
+        let count = Rc::new(());
+        let a: ThinDyn<dyn Fn() -> usize> = ThinBox::new_unsize({
+            let count = count.clone();
+            move || x.wrapping_add(Rc::strong_count(&count))
+        });
+        let b = ThinBox::<dyn Fn() -> usize>::from(Box::new(move || y.0) as Box<dyn Fn() -> usize>);
+        assert_eq!(std::mem::align_of::<usize>(), <ThinDyn<dyn Fn() -> usize>>::ALIGNMENT);
+
+        let mut c = Compact::new((a, Bits::<3>::new_masked(x)));
+        assert_eq!(x.wrapping_add(2), (c.get_ref().0)());
+
+        let s = ShyAtomic::new(Some(b));
+        c.get_mut().0 = s.swap(None).unwrap();
+        assert_eq!(1, Rc::strong_count(&count));
+        assert_eq!(y.0, (c.get_ref().0)());
+    }
+
+    #[test]
+    fn thin_shared(x in "\\PC{0,12}", y in vec(usize(), 0 .. 5)) {
+        let a = ThinArc::<str>::copy_from(&Arc::from(x.as_str()));
+        let s = ShyAtomic::new(a.clone());
+        assert!(ThinArc::ptr_eq(&a, &s.swap(ThinArc::from(x.as_str()))));
+        assert_eq!(x.as_str(), &*s.compare_exchange(&a, a.clone()).unwrap_err());
+
+        let count = Rc::new(());
+        let b: ThinRc<dyn Fn() -> usize> = ThinRc::new_unsize({
+            let count = count.clone();
+            move || Rc::strong_count(&count)
+        });
+        let c = Compact::new(Some(b.clone()));
+        assert_eq!(2, b());
+        drop(b);
+        assert_eq!(2, (c.get_clone().unwrap())());
+        drop(c);
+        assert_eq!(1, Rc::strong_count(&count));
+
+        let d = ThinArc::copy_from(&Arc::<[usize]>::from(y.as_slice()));
+        assert_eq!(y.as_slice(), &*d);
+        assert_eq!(&*ThinRc::new(y.clone()), &y);
+    }
+
+    #[test]
+    fn small_ints(x in num::i32::ANY, y in num::i32::ANY, z in num::u8::ANY) {
+        type I = SmallInt<32>;
+        let (a, b) = (I::new(x as isize).unwrap(), I::new(y as isize).unwrap());
+        assert_eq!(x.checked_add(y).map(|s| s as isize), a.checked_add(b).map(I::value));
+        assert_eq!(x.wrapping_mul(y) as isize, a.wrapping_mul(b).value());
+        assert_eq!([None, None], [I::new(I::MAX + 1), I::new(I::MIN - 1)]);
+
+        let u = SmallUint::<8>::new(z.into()).unwrap();
+        assert_eq!(z.wrapping_sub(1) as usize, u.wrapping_sub(SmallUint::new(1).unwrap()).value());
+
+        let c = Compact::new(Err::<Box<usize>, _>((a, true)));
+        assert_eq!(x as isize, c.get_ref().as_ref().unwrap_err().0.value());
+
+        let s = ShyAtomic::new(Ok::<_, Arc<usize>>(b));
+        assert_eq!(Ok(b), s.swap(Ok(a)));
+    }
+
+    #[test]
+    fn compact_values(x in compact_value(), y in num::isize::ANY) {
+        fn rebuild(x: &CompactValue) -> CompactValue {
+            match x.view() {
+                ValueRef::Null => CompactValue::null(),
+                ValueRef::Bool(x) => x.into(),
+                ValueRef::Int(x) => CompactValue::int(x).unwrap(),
+                ValueRef::Str(x) => x.into(),
+                ValueRef::Array(x) => x.iter().map(rebuild).collect(),
+                ValueRef::Map(x) => x.iter().map(|(k, v)| (k.clone(), rebuild(v))).collect(),
+            }
+        }
+
+        assert_eq!(size_of::<usize>(), size_of::<CompactValue>());
+        assert_eq!(x, x.clone());
+        assert_eq!(x, rebuild(&x));
+
+        let fits = (Int::MIN ..= Int::MAX).contains(&y);
+        assert_eq!(fits.then_some(y), CompactValue::int(y).and_then(|v| v.as_int()));
+
+        let list = vec![x.clone(), CompactValue::from(Some("x")), None::<bool>.into()];
+        let list = CompactValue::from(list);
+        let items = list.as_array().unwrap();
+        assert_eq!(Some(&x), items.first());
+        assert_eq!((Some("x"), true), (items[1].as_str(), items[2].is_null()));
+    }
+
+    #[test]
+    fn dispatch(x in any_expr(), env in usize(), by in usize()) {
+        let mut c = Compact::new(x.clone());
+        assert_eq!(size_of::<usize>(), size_of_val(&c));
+        assert_eq!(x.eval(env), c.eval(env));
+        assert_eq!(x.name(), c.name());
+
+        let mut y = x.clone();
+        y.bump(by);
+        c.bump(by);
+        assert_eq!(y, c.get_clone());
+        assert_eq!(y.name(), c.name());
+        assert_eq!(y.eval(env), c.eval(env));
+    }
+
+    #[test]
+    fn strong_or_weak(x in arc(aligned()), y in rc(usize()), tag in bool::ANY) {
+        let mut a = Compact::new((StrongOrWeak::Strong(x.clone()), tag));
+        a.get_mut().0.downgrade_in_place();
+        assert_eq!((1, tag), (Arc::strong_count(&x), a.get_ref().1));
+        assert!(a.get_mut().0.try_upgrade_in_place());
+        assert_eq!(Some(&x), a.get_ref().0.upgrade().as_ref());
+
+        let mut b = Compact::new(StrongOrWeak::Strong(y.clone()));
+        b.downgrade_in_place();
+        assert!(!b.get_ref().is_strong());
+        assert!(b.try_upgrade_in_place());
+        assert_eq!(2, Rc::strong_count(&y));
+        b.downgrade_in_place();
+        drop(y);
+        assert!(!b.try_upgrade_in_place());
+
+        let s = ShyAtomic::new(StrongOrWeak::<Arc<_>>::Weak(Arc::downgrade(&x)));
+        assert!(matches!(s.try_upgrade_by_swap(), Ok(true)));
+        assert_eq!(3, Arc::strong_count(&x));
+        assert!(s.downgrade_by_swap().is_ok());
+        assert_eq!(2, Arc::strong_count(&x));
+        drop(a);
+        drop(x);
+        assert!(matches!(s.try_upgrade_by_swap(), Ok(false)));
+    }
+
+    #[test]
+    fn borrowed(x in usize(), y in usize(), tag in bool::ANY) {
+        let (a, b) = (x, Aligned(y));
+        let mut c = Compact::<Result<&usize, &Aligned>>::new(Ok(&a));
+        assert_eq!(Ok(&x), c.get_clone().map_err(|b| b.0));
+        c.set(Err(&b));
+        assert_eq!(Err(y), c.get_clone().copied().map_err(|b| b.0));
+
+        let mut m = Aligned(x);
+        let mut d = Compact::new((MutRef::new(&mut m), tag));
+        d.get_mut().0 .0 = y;
+        let (r, s) = (d.get_ref(), d.get_ref());
+        assert_eq!((y, tag), (r.0 .0, s.1));
+        drop(d);
+        assert_eq!(y, m.0);
+    }
+
+    #[test]
+    fn pinned_values(x in usize(), y in usize(), tag in bool::ANY) {
+        let mut c = Compact::new((Box::pin(Unmovable(x, PhantomPinned)), tag));
+        c.get_mut().0.as_mut().bump(y);
+        assert_eq!((x.wrapping_add(y), tag), (c.get_ref().0 .0, c.get_ref().1));
+
+        let mut d = Compact::new(Box::pin(Unmovable(x, PhantomPinned)));
+        let addr = ptr::from_ref(&*d.get_pin_mut());
+        d.get_pin_mut().as_mut().bump(y);
+        assert_eq!(x.wrapping_add(y), d.get_pin_mut().0);
+        assert_eq!(addr, ptr::from_ref(&*d.get_ref().as_ref()));
+    }
+
+    #[test]
+    fn aligned_boxes(x in any::<u8>(), y in any::<u16>(), tag in bits::<4>()) {
+        let mut a = Compact::new((AlignedBox::<u8, 16>::from(Box::new(x)), tag));
+        *a.get_mut().0 = x.wrapping_add(1);
+        assert_eq!((x.wrapping_add(1), tag), (*a.get_ref().0, a.get_ref().1));
+        assert!(ptr::from_ref(&*a.get_ref().0).is_aligned_to(16));
+
+        let b = AlignedArc::<u16, 64>::new(y);
+        let s = ShyAtomic::new((b.clone(), Bits::<6>::new_masked(0)));
+        assert!(AlignedArc::ptr_eq(&b, &s.swap((AlignedArc::new(0), Bits::new_masked(1))).0));
+        assert_eq!(y, AlignedBox::<_, 8>::new(*b).into_inner());
+    }
+
+    #[test]
+    fn one_of(x in usize(), y in usize()) {
+        let a = Five::new(Arc::new(x));
+        assert_eq!(2, a.index());
+        assert!(a.is::<Arc<usize>>());
+        assert!(!a.is::<Rc<usize>>());
+        assert_eq!(Some(&x), a.get::<Arc<usize>>().as_deref().map(Deref::deref));
+        assert!(a.get::<Box<usize>>().is_none());
+
+        let a = a.into::<Rc<usize>>().unwrap_err();
+        assert_eq!(Ok(Arc::new(x)), a.into::<Arc<usize>>());
+
+        let b = Five::new(Box::new(y));
+        assert_eq!(b.clone(), b);
+        assert_ne!(Five::new(Rc::new(y)), b);
+    }
+
+    #[test]
+    fn prefix_tags(x in mixed(), y in wide()) {
+        assert_eq!(1, Mixed::ALIGNMENT);
+        assert_eq!(4, Wide::ALIGNMENT);
+        assert_eq!(1, Uneven::ALIGNMENT);
+
+        let ptr = Pointer::as_ptr(&x);
+        assert_eq!(ptr.addr() & 1 == 0, x.is::<Box<u16>>());
+
+        let ptr = Pointer::as_ptr(&y);
+        let none = y.get::<Option<Box<usize>>>().is_some_and(|x| x.is_none());
+        assert_eq!(ptr.is_null(), none);
+    }
+
+    #[test]
+    fn tag_order(x in arc(aligned()), y in bits::<2>(), z in bits::<3>()) {
+        let ptr = Pointer::as_ptr(&x);
+        let nested = Pointer::into_ptr(((x.clone(), y), z));
+        let flat = Pointer::into_ptr((x, y, z));
+        assert_eq!(nested, flat);
+        assert_eq!(ptr.addr() | (y.value() << 4) | (z.value() << 1), flat.addr());
+
+        unsafe {
+            <((Arc<Aligned>, Bits<2>), Bits<3>)>::from_ptr(nested).assume_owned();
+            <(Arc<Aligned>, Bits<2>, Bits<3>)>::from_ptr(flat).assume_owned();
+        }
+    }
+
     #[test]
     fn shy_atomic([x, y, z, w] in array(compound_cip())) {
         let a = ShyAtomic::new(x.clone());
@@ -287,3 +1276,11 @@ proptest! {
         }
     }
 }
+
+
+#[test]
+fn flags_mask() {
+    assert_eq!(0, Flags::<Num<0>>::MASK);
+    assert_eq!(0b111, Flags::<Num<3>>::MASK);
+    assert_eq!(usize::MAX, Flags::<Num<{ usize::BITS as usize }>>::MASK);
+}